@@ -0,0 +1,275 @@
+use crate::imports::*;
+
+/// Group NGINX workers run as (Debian/Ubuntu)
+const NGINX_GROUP: &str = "www-data";
+/// Folder holding htpasswd files (`khost_<zone>.htpasswd`)
+const HTPASSWD_FOLDER: &str = "/etc/nginx";
+
+/// IP allow/deny lists and basic authentication for a proxy location
+#[derive(Default, Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Access {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+    #[serde(default)]
+    pub auth: Option<BasicAuth>,
+}
+
+impl Access {
+    pub fn is_enabled(&self) -> bool {
+        !self.allow.is_empty() || !self.deny.is_empty() || self.auth.is_some()
+    }
+}
+
+impl Display for Access {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let list = |list: &[String]| {
+            if list.is_empty() {
+                "-".to_string()
+            } else {
+                list.join(" ")
+            }
+        };
+        write!(
+            f,
+            "allow: {}, deny: {}",
+            list(&self.allow),
+            list(&self.deny)
+        )?;
+        match &self.auth {
+            Some(auth) => write!(f, ", auth: '{}' ({} users)", auth.realm, auth.users.len()),
+            None => write!(f, ", auth: -"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct BasicAuth {
+    pub realm: String,
+    pub users: Vec<Credential>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Credential {
+    pub user: String,
+    /// APR1 (htpasswd-compatible) password hash
+    pub hash: String,
+}
+
+impl BasicAuth {
+    pub fn htpasswd(&self) -> String {
+        self.users
+            .iter()
+            .map(|credential| format!("{}:{}\n", credential.user, credential.hash))
+            .collect()
+    }
+}
+
+/// htpasswd file for the proxy location identified by the `zone` name
+pub fn htpasswd_filename(zone: &str) -> PathBuf {
+    PathBuf::from(HTPASSWD_FOLDER).join(format!("{zone}.htpasswd"))
+}
+
+/// Remove htpasswd files of locations no longer protected by basic authentication
+pub fn remove_stale_htpasswd(zones: &[String]) -> Result<()> {
+    let current = zones
+        .iter()
+        .map(|zone| htpasswd_filename(zone))
+        .collect::<Vec<_>>();
+    let Ok(entries) = fs::read_dir(HTPASSWD_FOLDER) else {
+        return Ok(());
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let is_khost = path.file_name().is_some_and(|name| {
+            let name = name.to_string_lossy();
+            name.starts_with("khost_") && name.ends_with(".htpasswd")
+        });
+        if is_khost && !current.contains(&path) {
+            sudo::fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Write the htpasswd file readable only by root and NGINX workers
+pub fn store_htpasswd(zone: &str, auth: &BasicAuth) -> Result<()> {
+    sudo::fs::write_with_permissions(
+        htpasswd_filename(zone),
+        auth.htpasswd(),
+        format!("root:{NGINX_GROUP}"),
+        "640",
+    )
+}
+
+pub fn hash_password(password: &str) -> Result<String> {
+    let hash = cmd!("openssl", "passwd", "-apr1", "-stdin")
+        .stdin_bytes(format!("{password}\n"))
+        .read()?;
+    Ok(hash.trim().to_string())
+}
+
+fn ask_cidrs<S: Display>(prompt: S, current: &[String]) -> Result<Vec<String>> {
+    let input: String = cliclack::input(prompt)
+        .required(false)
+        .default_input(&current.join(" "))
+        .validate(|input: &String| {
+            input
+                .split_whitespace()
+                .try_for_each(network::validate_cidr)
+                .map_err(|err| err.to_string())
+        })
+        .interact()?;
+    Ok(input.split_whitespace().map(String::from).collect())
+}
+
+fn ask_credential() -> Result<Credential> {
+    let user: String = cliclack::input("Username:")
+        .validate(|input: &String| {
+            if input.is_empty() || input.contains(':') || input.contains(char::is_whitespace) {
+                Err("Please enter a valid username".to_string())
+            } else {
+                Ok(())
+            }
+        })
+        .interact()?;
+    let password1 = cliclack::password("Password:").interact()?;
+    let password2 = cliclack::password("Confirm password:").interact()?;
+    if password1 != password2 {
+        return Err(Error::PasswordsDoNotMatch);
+    }
+
+    Ok(Credential {
+        user,
+        hash: hash_password(&password1)?,
+    })
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Operation {
+    Allow,
+    Deny,
+    Auth,
+    AddUser,
+    RemoveUser,
+    Reset,
+    Done,
+}
+
+pub fn configure(ctx: &mut Context) -> Result<()> {
+    if ctx.config.public {
+        log::warning(
+            "This node participates in the public node network.\nRestricting access will prevent public clients from connecting.",
+        )?;
+    }
+
+    let mut paths = ctx
+        .proxy_configs(ctx)
+        .into_iter()
+        .map(|proxy| proxy.path)
+        .collect::<Vec<_>>();
+    // metrics are proxied only once restricted, so offer the endpoint regardless
+    if ctx.config.exporter.enabled() && !paths.iter().any(|path| path == exporter::PROXY_PATH) {
+        paths.push(exporter::PROXY_PATH.to_string());
+    }
+    if paths.is_empty() {
+        log::warning("No proxied endpoints are configured")?;
+        return Ok(());
+    }
+
+    let mut selector = cliclack::select("Select endpoint to configure access");
+    for path in paths.iter() {
+        let hint = if path == exporter::PROXY_PATH && exporter::proxy_access(ctx).is_none() {
+            "not proxied until restricted".to_string()
+        } else {
+            ctx.config.nginx.access(path).to_string()
+        };
+        selector = selector.item(path.clone(), path, hint);
+    }
+    let path = selector.interact()?;
+
+    let mut access = ctx.config.nginx.access(&path);
+    loop {
+        let mut selector = cliclack::select(format!("{path}: {access}"))
+            .item(Operation::Allow, "Edit allowed addresses", "IP or CIDR")
+            .item(Operation::Deny, "Edit denied addresses", "IP or CIDR");
+        if access.auth.is_some() {
+            selector = selector
+                .item(Operation::AddUser, "Add user", "")
+                .item(Operation::RemoveUser, "Remove users", "")
+                .item(Operation::Auth, "Disable basic authentication", "");
+        } else {
+            selector = selector.item(Operation::Auth, "Enable basic authentication", "");
+        }
+        selector = selector
+            .item(Operation::Reset, "Remove all restrictions", "")
+            .item(Operation::Done, "Done", "");
+
+        match selector.interact()? {
+            Operation::Allow => {
+                access.allow = ask_cidrs(
+                    "Allowed addresses (space separated, empty to allow all):",
+                    &access.allow,
+                )?;
+            }
+            Operation::Deny => {
+                access.deny = ask_cidrs("Denied addresses (space separated):", &access.deny)?;
+            }
+            Operation::Auth => {
+                if access.auth.is_some() {
+                    access.auth = None;
+                } else {
+                    let realm: String = cliclack::input("Authentication realm:")
+                        .default_input("kHOST")
+                        .validate(|input: &String| {
+                            if input.is_empty() || input.contains('"') {
+                                Err("Please enter a valid realm".to_string())
+                            } else {
+                                Ok(())
+                            }
+                        })
+                        .interact()?;
+                    let credential = ask_credential()?;
+                    access.auth = Some(BasicAuth {
+                        realm,
+                        users: vec![credential],
+                    });
+                }
+            }
+            Operation::AddUser => {
+                let credential = ask_credential()?;
+                if let Some(auth) = access.auth.as_mut() {
+                    auth.users
+                        .retain(|existing| existing.user != credential.user);
+                    auth.users.push(credential);
+                }
+            }
+            Operation::RemoveUser => {
+                if let Some(auth) = access.auth.as_mut() {
+                    let mut selector =
+                        cliclack::multiselect("Select users to remove (ESC to cancel)");
+                    for credential in auth.users.iter() {
+                        selector = selector.item(credential.user.clone(), &credential.user, "");
+                    }
+                    if let Ok(users) = selector.interact() {
+                        auth.users
+                            .retain(|credential| !users.contains(&credential.user));
+                    }
+                    if auth.users.is_empty() {
+                        log::warning("No users left, basic authentication disabled")?;
+                        access.auth = None;
+                    }
+                }
+            }
+            Operation::Reset => {
+                access = Access::default();
+            }
+            Operation::Done => break,
+        }
+    }
+
+    ctx.config.nginx.set_access(&path, access);
+    ctx.config.save()?;
+
+    Ok(())
+}
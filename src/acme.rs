@@ -0,0 +1,301 @@
+use crate::imports::*;
+use nginx::prelude::*;
+
+/// Let's Encrypt production directory
+pub const LETSENCRYPT_DIRECTORY: &str = "https://acme-v02.api.letsencrypt.org/directory";
+
+const CERT_NAME: &str = "khost";
+const RENEWAL_UNIT: &str = "khost-acme-renew";
+const WEBROOT: &str = "/var/www/khost-acme";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Config {
+    pub enabled: bool,
+    /// ACME directory URL (Let's Encrypt or a local stand-in such as Pebble)
+    pub directory: String,
+    /// CA bundle used to verify the ACME directory (e.g. Pebble's minica root)
+    #[serde(default)]
+    pub ca_bundle: Option<String>,
+    pub email: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: LETSENCRYPT_DIRECTORY.to_string(),
+            ca_bundle: None,
+            email: None,
+        }
+    }
+}
+
+pub fn folder() -> PathBuf {
+    data_folder().join("acme")
+}
+
+/// Folder served by NGINX at `/.well-known/acme-challenge/`
+pub fn webroot() -> PathBuf {
+    PathBuf::from(WEBROOT)
+}
+
+pub fn certs() -> Certs {
+    let live = folder().join("live").join(CERT_NAME);
+    Certs::new(live.join("privkey.pem"), live.join("fullchain.pem"))
+}
+
+pub fn version() -> Option<String> {
+    cmd!("certbot", "--version")
+        .read()
+        .ok()
+        .map(|s| s.trim().to_string())
+}
+
+fn certbot_args() -> Vec<String> {
+    let folder = folder();
+    vec![
+        format!("--config-dir={}", folder.display()),
+        format!("--work-dir={}", folder.join("work").display()),
+        format!("--logs-dir={}", folder.join("logs").display()),
+    ]
+}
+
+fn certbot<A>(config: &Config, args: A) -> Expression
+where
+    A: IntoIterator<Item = String>,
+{
+    let args = args.into_iter().chain(certbot_args()).collect::<Vec<_>>();
+    let expr = cmd("certbot", args);
+    match config.ca_bundle.as_ref() {
+        Some(ca_bundle) => expr.env("REQUESTS_CA_BUNDLE", ca_bundle),
+        None => expr,
+    }
+}
+
+pub fn install(ctx: &Context) -> Result<()> {
+    if version().is_none() {
+        step("Installing certbot...", || {
+            sudo!("apt", "install", "-y", "certbot").run()
+        })?;
+    }
+
+    step("Creating ACME challenge folder...", || {
+        sudo!("mkdir", "-p", webroot()).run()?;
+        sudo!("chown", format!("{}:", ctx.username), webroot()).run()?;
+        sudo!("chmod", "755", webroot()).run()?;
+        Ok(())
+    })?;
+
+    fs::create_dir_all(folder())?;
+
+    Ok(())
+}
+
+/// Domains that can be validated using HTTP-01 challenge (wildcards are not supported)
+pub fn domains(ctx: &Context) -> Vec<String> {
+    ctx.config
+        .fqdn
+        .iter()
+        .flatten()
+        .filter(|fqdn| !fqdn.starts_with("*."))
+        .cloned()
+        .collect()
+}
+
+pub fn configure(ctx: &mut Context) -> Result<()> {
+    let directory: String = cliclack::input("ACME directory URL:")
+        .default_input(&ctx.config.acme.directory)
+        .validate(|input: &String| {
+            if input.starts_with("https://") || input.starts_with("http://") {
+                Ok(())
+            } else {
+                Err("Please enter a valid URL".to_string())
+            }
+        })
+        .interact()?;
+
+    let mut input = cliclack::input("Contact email (optional):").required(false);
+    if let Some(email) = ctx.config.acme.email.as_ref() {
+        input = input.default_input(email);
+    }
+    let email: String = input.interact()?;
+    let email = email.trim().to_string();
+
+    ctx.config.acme.directory = directory.trim().to_string();
+    ctx.config.acme.email = (!email.is_empty()).then_some(email);
+
+    if domains(ctx).is_empty() {
+        let fqdns = config::fqdn("Enter fully qualified domain names (FQDN) for the certificate:")?;
+        ctx.config.fqdn = Some(fqdn::parse(&fqdns)?);
+    }
+
+    ctx.config.save()?;
+
+    Ok(())
+}
+
+/// Obtain a certificate via ACME HTTP-01 challenge and enable it in NGINX
+pub fn issue(ctx: &mut Context) -> Result<()> {
+    let domains = domains(ctx);
+    if domains.is_empty() {
+        return Err(Error::custom(
+            "No FQDNs suitable for ACME HTTP-01 validation are configured",
+        ));
+    }
+    for domain in domains
+        .iter()
+        .filter(|domain| !fqdn::has_public_suffix(domain))
+    {
+        log::warning(format!(
+            "`{domain}` has an unknown top-level domain; ACME validation is likely to fail"
+        ))?;
+    }
+
+    install(ctx)?;
+
+    // serve the challenge location while the certificate is being issued
+    ctx.config.acme.enabled = true;
+    nginx::reconfigure(ctx)?;
+
+    let config = &ctx.config.acme;
+    let mut args = vec![
+        "certonly".to_string(),
+        "--non-interactive".to_string(),
+        "--agree-tos".to_string(),
+        "--webroot".to_string(),
+        format!("--webroot-path={}", webroot().display()),
+        format!("--cert-name={CERT_NAME}"),
+        format!("--server={}", config.directory),
+    ];
+    match config.email.as_ref() {
+        Some(email) => args.push(format!("--email={email}")),
+        None => args.push("--register-unsafely-without-email".to_string()),
+    }
+    for domain in domains.iter() {
+        args.push(format!("--domain={domain}"));
+    }
+
+    let issued = step(
+        format!("Requesting certificate for {}", domains.join(", ")),
+        || certbot(config, args).run(),
+    );
+
+    if let Err(err) = issued {
+        ctx.config.acme.enabled = false;
+        nginx::reconfigure(ctx)?;
+        return Err(err);
+    }
+
+    ctx.config.nginx.enable_certs(certs());
+    ctx.config.save()?;
+
+    install_renewal_timer(ctx)?;
+
+    log::success("ACME certificate issued")?;
+
+    Ok(())
+}
+
+pub fn disable(ctx: &mut Context) -> Result<()> {
+    remove_renewal_timer()?;
+    ctx.config.acme.enabled = false;
+    ctx.config.save()?;
+    Ok(())
+}
+
+struct RenewalUnit {
+    user: String,
+    ca_bundle: Option<String>,
+    exec_start: String,
+}
+
+impl RenewalUnit {
+    fn new(ctx: &Context) -> Self {
+        let exec_start = ["/usr/bin/certbot", "renew", "--quiet"]
+            .into_iter()
+            .map(String::from)
+            .chain(certbot_args())
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Self {
+            user: ctx.username.clone(),
+            ca_bundle: ctx.config.acme.ca_bundle.clone(),
+            exec_start,
+        }
+    }
+}
+
+impl Display for RenewalUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[Unit]")?;
+        writeln!(f, "Description=kHOST ACME certificate renewal")?;
+        writeln!(f)?;
+        writeln!(f, "[Service]")?;
+        writeln!(f, "Type=oneshot")?;
+        writeln!(f, "User={}", self.user)?;
+        if let Some(ca_bundle) = &self.ca_bundle {
+            writeln!(f, "Environment=REQUESTS_CA_BUNDLE={ca_bundle}")?;
+        }
+        writeln!(f, "ExecStart={}", self.exec_start)?;
+        // `+` runs the reload with full privileges
+        writeln!(f, "ExecStartPost=+/usr/sbin/nginx -s reload")?;
+        Ok(())
+    }
+}
+
+struct RenewalTimer;
+
+impl Display for RenewalTimer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[Unit]")?;
+        writeln!(f, "Description=kHOST ACME certificate renewal timer")?;
+        writeln!(f)?;
+        writeln!(f, "[Timer]")?;
+        writeln!(f, "OnCalendar=*-*-* 03,15:00:00")?;
+        writeln!(f, "RandomizedDelaySec=1h")?;
+        writeln!(f, "Persistent=true")?;
+        writeln!(f)?;
+        writeln!(f, "[Install]")?;
+        writeln!(f, "WantedBy=timers.target")?;
+        Ok(())
+    }
+}
+
+pub fn install_renewal_timer(ctx: &Context) -> Result<()> {
+    step("Installing certificate renewal timer...", || {
+        sudo::fs::write(
+            systemd::service_path(RENEWAL_UNIT),
+            RenewalUnit::new(ctx).to_string(),
+        )?;
+        sudo::fs::write(systemd::timer_path(RENEWAL_UNIT), RenewalTimer.to_string())?;
+        systemd::daemon_reload()?;
+        sudo!(
+            "systemctl",
+            "enable",
+            "--now",
+            format!("{RENEWAL_UNIT}.timer")
+        )
+        .run()
+    })
+}
+
+pub fn remove_renewal_timer() -> Result<()> {
+    let timer_path = systemd::timer_path(RENEWAL_UNIT);
+    if timer_path.exists() {
+        step("Removing certificate renewal timer...", || {
+            sudo!(
+                "systemctl",
+                "disable",
+                "--now",
+                format!("{RENEWAL_UNIT}.timer")
+            )
+            .unchecked()
+            .run()?;
+            sudo::fs::remove_file(timer_path)?;
+            sudo::fs::remove_file(systemd::service_path(RENEWAL_UNIT))?;
+            systemd::daemon_reload()
+        })?;
+    }
+    Ok(())
+}
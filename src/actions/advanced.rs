@@ -17,6 +17,10 @@ pub enum Advanced {
     SudoersEntry,
     #[describe("Generate resolver key")]
     ResolverKey,
+    #[describe("Create support bundle")]
+    SupportBundle,
+    #[describe("Restore configuration")]
+    Restore,
 }
 
 impl Action for Advanced {
@@ -100,8 +104,8 @@ impl Action for Advanced {
                             .name();
                         let origin = git::create_origin(name)?;
                         match &service.kind {
-                            ServiceKind::Kaspad(network) => {
-                                kaspad::find_config_by_network(ctx, network)
+                            ServiceKind::Kaspad(_) => {
+                                kaspad::find_config_by_service_detail(ctx, service)
                                     .expect("Kaspad config not found")
                                     .set_origin(origin);
                                 ctx.config.save()?;
@@ -137,6 +141,14 @@ impl Action for Advanced {
                 resolver::init_resolver_config(ctx)?;
                 Ok(true)
             }
+            Advanced::SupportBundle => {
+                support::bundle(ctx)?;
+                Ok(true)
+            }
+            Advanced::Restore => {
+                history::restore(ctx)?;
+                Ok(true)
+            }
         }
     }
 }
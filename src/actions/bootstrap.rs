@@ -24,8 +24,13 @@ impl Action for Bootstrap {
                         resolver::init_resolver_config(ctx).ok();
                     }
 
+                    hardware::report(ctx)?;
                     kaspad::select_networks(ctx)?;
 
+                    if !ports::confirm_install(ctx)? {
+                        return Ok(false);
+                    }
+
                     base::install(ctx, false)?;
                     ctx.config.bootstrap = true;
                     ctx.config.save()?;
@@ -1,5 +1,3 @@
-use std::iter::once;
-
 use crate::imports::*;
 use nginx::prelude::*;
 
@@ -26,6 +24,22 @@ pub enum Configure {
     View,
     #[describe("Configure SSL certificates")]
     Tls,
+    #[describe("Configure domain names (FQDN)")]
+    Domains,
+    #[describe("Configure rate limits")]
+    Limits,
+    #[describe("Configure endpoint access")]
+    Access,
+    #[describe("Configure gRPC exposure")]
+    Grpc,
+    #[describe("Manage node instances")]
+    Instances,
+    #[describe("Configure watchdog")]
+    Watchdog,
+    #[describe("Configure alerts")]
+    Alerts,
+    #[describe("Resolve NGINX site conflicts")]
+    Sites,
 }
 
 impl Action for Configure {
@@ -76,8 +90,31 @@ impl Action for Configure {
                     {
                         ctx.config.nginx.disable_certs();
                         ctx.config.save()?;
+                        if ctx.config.acme.enabled {
+                            acme::disable(ctx)?;
+                        }
                         log::info("SSL certificates disabled")?;
                         reconfigure = true;
+                    } else {
+                        if pki::is_managed(ctx)
+                            && confirm("Export local CA certificate?")
+                                .initial_value(false)
+                                .interact()?
+                        {
+                            pki::export_ca()?;
+                        }
+
+                        let hsts = ctx.config.nginx.hsts();
+                        let prompt = if hsts {
+                            "Disable HTTP Strict Transport Security (HSTS)?"
+                        } else {
+                            "Enable HTTP Strict Transport Security (HSTS)?\nBrowsers will refuse plain HTTP connections to this host."
+                        };
+                        if confirm(prompt).initial_value(false).interact()? {
+                            ctx.config.nginx.set_hsts(!hsts);
+                            ctx.config.save()?;
+                            reconfigure = true;
+                        }
                     }
                 } else {
                     log::info("No SSL certificates are currently configured")?;
@@ -104,45 +141,66 @@ impl Action for Configure {
                             .collect::<Vec<_>>();
 
                         if located.is_empty() {
-                            log::error("No SSL certificates found in data or home folder")?;
-                        } else {
-                            let mut selector = cliclack::select(
-                                "Select a folder containing existing SSL certificates",
+                            log::info("No SSL certificates found in data or home folder")?;
+                        }
+
+                        let mut selector = cliclack::select("Select SSL certificate source");
+                        for folder in located.iter() {
+                            selector = selector.item(
+                                CertSource::Folder(folder.clone()),
+                                folder.display(),
+                                "existing certificates",
                             );
-                            for folder in located.iter() {
-                                selector =
-                                    selector.item(Some(folder.clone()), folder.display(), "");
+                        }
+                        selector = selector
+                            .item(
+                                CertSource::Acme,
+                                "Obtain certificate via ACME",
+                                "Let's Encrypt HTTP-01 challenge",
+                            )
+                            .item(
+                                CertSource::SelfSigned,
+                                "Generate self-signed certificate",
+                                "local CA for private clusters",
+                            )
+                            .item(CertSource::Custom, "Specify custom certificate files", "");
+                        let selected = selector.interact()?;
+
+                        match selected {
+                            CertSource::Folder(folder) => {
+                                let key = folder.join("server.key");
+                                let crt = folder.join("server.crt");
+                                let certs = Certs::new(key, crt);
+                                ctx.config.nginx.enable_certs(certs);
+                                ctx.config.save()?;
+                                log::info("SSL certificates enabled")?;
                             }
-                            selector = selector.item(None, "Specify custom certificate files", "");
-                            let selected = selector.interact()?;
-
-                            match selected {
-                                Some(folder) => {
-                                    let key = folder.join("server.key");
-                                    let crt = folder.join("server.crt");
-                                    let certs = Certs::new(key, crt);
-                                    ctx.config.nginx.enable_certs(certs);
-                                    ctx.config.save()?;
-                                    log::info("SSL certificates enabled")?;
-                                }
-                                None => {
-                                    let key = ask_file_path(
-                                        "Enter path to certificate key file (*.key)",
-                                        tls::load_private_key,
-                                    )?;
-                                    let crt = ask_file_path(
-                                        "Enter path to certificate file (*.crt)",
-                                        tls::load_certs,
-                                    )?;
-
-                                    let certs = Certs::new(key, crt);
-                                    ctx.config.nginx.enable_certs(certs);
-                                    ctx.config.save()?;
-                                    log::info("SSL certificates enabled")?;
-                                }
+                            CertSource::Acme => {
+                                acme::configure(ctx)?;
+                                acme::issue(ctx)?;
+                                log::info("SSL certificates enabled")?;
+                            }
+                            CertSource::SelfSigned => {
+                                pki::configure(ctx)?;
+                                log::info("SSL certificates enabled")?;
+                            }
+                            CertSource::Custom => {
+                                let key = ask_file_path(
+                                    "Enter path to certificate key file (*.key)",
+                                    tls::load_private_key,
+                                )?;
+                                let crt = ask_file_path(
+                                    "Enter path to certificate file (*.crt)",
+                                    tls::load_certs,
+                                )?;
+
+                                let certs = Certs::new(key, crt);
+                                ctx.config.nginx.enable_certs(certs);
+                                ctx.config.save()?;
+                                log::info("SSL certificates enabled")?;
                             }
-                            reconfigure = true;
                         }
+                        reconfigure = true;
                     }
                 }
 
@@ -153,12 +211,7 @@ impl Action for Configure {
                 Ok(true)
             }
             Configure::View => {
-                let configs = ctx
-                    .managed_active_services()
-                    .into_iter()
-                    .map(|detail| systemd::service_path(detail.name.as_str()))
-                    .chain(once(nginx::config_filename()))
-                    .collect::<Vec<_>>();
+                let configs = support::config_files(ctx);
 
                 configs.iter().for_each(|path| {
                     if path.exists() {
@@ -175,9 +228,62 @@ impl Action for Configure {
 
                 Ok(true)
             }
+            Configure::Domains => {
+                actions::Domains::select(ctx)?;
+                Ok(true)
+            }
+            Configure::Limits => {
+                nginx::configure_limits(ctx)?;
+                nginx::reconfigure(ctx)?;
+                Ok(true)
+            }
+            Configure::Access => {
+                access::configure(ctx)?;
+                nginx::reconfigure(ctx)?;
+                Ok(true)
+            }
+            Configure::Grpc => {
+                kaspad::configure_grpc(ctx)?;
+                nginx::reconfigure(ctx)?;
+                Ok(true)
+            }
+            Configure::Instances => {
+                let add = cliclack::select("Manage Kaspa p2p node instances")
+                    .item(true, "Add node instance", "load-balanced by NGINX")
+                    .item(false, "Remove node instance", "")
+                    .interact()?;
+                if add {
+                    kaspad::add_instance(ctx)?;
+                } else {
+                    kaspad::remove_instance(ctx)?;
+                }
+                nginx::reconfigure(ctx)?;
+                Ok(true)
+            }
+            Configure::Watchdog => {
+                watchdog::configure(ctx)?;
+                Ok(true)
+            }
+            Configure::Alerts => {
+                alerts::configure(ctx)?;
+                Ok(true)
+            }
+            Configure::Sites => {
+                if sites::conflicts(ctx).is_empty() {
+                    log::success("No conflicting NGINX sites found")?;
+                } else {
+                    sites::resolve(ctx)?;
+                }
+                Ok(true)
+            }
             Configure::Rebuild => {
                 kaspad::reconfigure(ctx, true)?;
                 resolver::reconfigure(ctx, true)?;
+                exporter::reconfigure(ctx)?;
+                watchdog::reconfigure(ctx)?;
+                alerts::reconfigure(ctx)?;
+                storage::reconfigure(ctx)?;
+                bandwidth::reconfigure(ctx)?;
                 nginx::reconfigure(ctx)?;
                 Ok(true)
             }
@@ -200,6 +306,14 @@ impl Action for Configure {
     }
 }
 
+#[derive(Debug, Clone, Eq, PartialEq)]
+enum CertSource {
+    Folder(PathBuf),
+    Acme,
+    SelfSigned,
+    Custom,
+}
+
 pub fn ask_file_path<S, F, T>(prompt: S, f: F) -> Result<String>
 where
     S: Display,
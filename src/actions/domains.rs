@@ -0,0 +1,90 @@
+use crate::imports::*;
+
+#[derive(Describe, Eq, PartialEq, Debug, Clone, Copy)]
+#[caption = "Domain names (FQDN)"]
+pub enum Domains {
+    #[describe("Back")]
+    Back,
+    #[describe("List domain names")]
+    List,
+    #[describe("Add domain names")]
+    Add,
+    #[describe("Remove domain names")]
+    Remove,
+}
+
+impl Action for Domains {
+    fn main(&self, ctx: &mut Context) -> Result<bool> {
+        match self {
+            Domains::Back => Ok(false),
+            Domains::List => {
+                let configured = fqdn::configured(ctx);
+                let configured = if configured.is_empty() {
+                    style("none").dim().to_string()
+                } else {
+                    configured.join("\n")
+                };
+
+                if ctx.config.public {
+                    log::info(format!(
+                        "Public node network domains (served):\n{}\n\nConfigured domains (not used in public mode):\n{}",
+                        fqdn::public().join("\n"),
+                        configured
+                    ))?;
+                } else {
+                    log::info(format!(
+                        "Configured domains:\n{configured}\n\nNGINX server name: {}",
+                        fqdn::flatten(&fqdn::get(ctx))
+                    ))?;
+                }
+
+                Ok(true)
+            }
+            Domains::Add => {
+                let fqdns = config::fqdn("Enter domain names to add (space separated):")?;
+                if fqdn::add(ctx, fqdn::parse(&fqdns)?)? {
+                    log::success("Domain names updated")?;
+                    reconfigure(ctx)?;
+                } else {
+                    log::info("Domain names are already configured")?;
+                }
+                Ok(true)
+            }
+            Domains::Remove => {
+                let configured = fqdn::configured(ctx);
+                if configured.is_empty() {
+                    log::warning("No domain names are configured")?;
+                    return Ok(true);
+                }
+
+                let mut selector =
+                    cliclack::multiselect("Select domain names to remove (ESC to cancel)");
+                for fqdn in configured.iter() {
+                    selector = selector.item(fqdn.clone(), fqdn, "");
+                }
+
+                match selector.interact() {
+                    Ok(selected) => {
+                        if fqdn::remove(ctx, &selected)? {
+                            log::success("Domain names updated")?;
+                            reconfigure(ctx)?;
+                        }
+                    }
+                    Err(_) => {
+                        println!();
+                    }
+                }
+                Ok(true)
+            }
+        }
+    }
+}
+
+fn reconfigure(ctx: &Context) -> Result<()> {
+    if ctx.config.public {
+        log::info("Configured domain names are not used while in public node network mode")?;
+        Ok(())
+    } else {
+        nginx::reconfigure(ctx)
+    }
+}
@@ -10,6 +10,8 @@ mod advanced;
 pub use advanced::*;
 mod configure;
 pub use configure::*;
+mod domains;
+pub use domains::*;
 mod status;
 pub use status::*;
 mod update;
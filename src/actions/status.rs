@@ -5,6 +5,8 @@ use crate::imports::*;
 pub enum Status {
     #[describe("Back")]
     Back,
+    #[describe("Live dashboard")]
+    Top,
     #[describe("Kaspa p2p node status")]
     Kaspad,
     #[describe("Resolver status")]
@@ -13,6 +15,14 @@ pub enum Status {
     Nginx,
     #[describe("Bandwidth usage")]
     Vnstat,
+    #[describe("RPC usage")]
+    Usage,
+    #[describe("Node performance")]
+    Perf,
+    #[describe("Hardware assessment")]
+    Hardware,
+    #[describe("Watchdog history")]
+    Watchdog,
     #[describe("View service logs")]
     ViewLogs,
     #[describe("Follow service logs")]
@@ -91,8 +101,27 @@ impl Action for Status {
                 Ok(true)
             }
             Status::Vnstat => {
-                let stats = cmd!("vnstat", "--days").read()?;
-                log::info(stats)?;
+                bandwidth::report(ctx)?;
+                Ok(true)
+            }
+            Status::Usage => {
+                usage::report(ctx)?;
+                Ok(true)
+            }
+            Status::Top => {
+                top::run(ctx)?;
+                Ok(true)
+            }
+            Status::Perf => {
+                perf::report(ctx)?;
+                Ok(true)
+            }
+            Status::Hardware => {
+                hardware::report(ctx)?;
+                Ok(true)
+            }
+            Status::Watchdog => {
+                watchdog::report()?;
                 Ok(true)
             }
         }
@@ -0,0 +1,872 @@
+use crate::imports::*;
+use chrono::{DateTime, Local, Timelike};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// Template unit started by `OnFailure=` of monitored services
+pub const SERVICE_NAME: &str = "khost-alert";
+/// Periodic certificate and disk space checks
+const CHECK_UNIT: &str = "khost-alert-check";
+/// Drop-in file name installed for each monitored service
+const DROPIN: &str = "khost-alert.conf";
+
+/// Network timeout for webhook and SMTP delivery
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Plain SMTP relay (e.g. a local Postfix instance); no TLS or authentication
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Smtp {
+    pub host: String,
+    pub port: u16,
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+impl Default for Smtp {
+    fn default() -> Self {
+        Self {
+            host: "127.0.0.1".to_string(),
+            port: 25,
+            from: "khost@localhost".to_string(),
+            to: vec![],
+        }
+    }
+}
+
+/// Local hours during which alerts are suppressed; may wrap past midnight
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct QuietHours {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl QuietHours {
+    pub fn contains(&self, hour: u32) -> bool {
+        if self.start <= self.end {
+            hour >= self.start && hour < self.end
+        } else {
+            hour >= self.start || hour < self.end
+        }
+    }
+}
+
+impl Display for QuietHours {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{:02}:00-{:02}:00", self.start, self.end)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Config {
+    /// URL receiving a JSON payload via HTTP POST
+    pub webhook: Option<String>,
+    pub smtp: Option<Smtp>,
+    /// Executable receiving the alert in `KHOST_ALERT_*` variables and as JSON on stdin
+    pub script: Option<String>,
+    /// Seconds before an identical alert is sent again
+    pub dedup: u64,
+    pub quiet_hours: Option<QuietHours>,
+    /// Alert when available disk space falls below this percentage
+    pub disk_threshold: u8,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            webhook: None,
+            smtp: None,
+            script: None,
+            dedup: 3600,
+            quiet_hours: None,
+            disk_threshold: 10,
+        }
+    }
+}
+
+impl Config {
+    /// Alerts are active when at least one sink is configured
+    pub fn enabled(&self) -> bool {
+        self.webhook.is_some() || self.smtp.is_some() || self.script.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Kind {
+    UnitFailed,
+    UpdateFailed,
+    CertExpiring,
+    LowDisk,
+    WatchdogRestart,
+    Test,
+}
+
+impl Display for Kind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::UnitFailed => write!(f, "service failed"),
+            Kind::UpdateFailed => write!(f, "update failed"),
+            Kind::CertExpiring => write!(f, "certificate expiring"),
+            Kind::LowDisk => write!(f, "low disk space"),
+            Kind::WatchdogRestart => write!(f, "watchdog restart"),
+            Kind::Test => write!(f, "test alert"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Alert {
+    pub kind: Kind,
+    pub subject: String,
+    pub message: String,
+    pub host: String,
+    pub time: DateTime<Local>,
+}
+
+impl Alert {
+    pub fn new<S: Display, M: Display>(kind: Kind, subject: S, message: M) -> Self {
+        let host = cmd!("hostname")
+            .read()
+            .map(|host| host.trim().to_string())
+            .unwrap_or_else(|_| "localhost".to_string());
+        Self {
+            kind,
+            subject: subject.to_string(),
+            message: message.to_string(),
+            host,
+            time: Local::now(),
+        }
+    }
+
+    /// Identity used for de-duplication
+    fn key(&self) -> String {
+        format!("{:?}:{}", self.kind, self.subject)
+    }
+
+    pub fn summary(&self) -> String {
+        format!("[kHOST {}] {}: {}", self.host, self.kind, self.subject)
+    }
+}
+
+impl Display for Alert {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.summary())?;
+        writeln!(f, "Time: {}", self.time.format("%Y-%m-%d %H:%M:%S %Z"))?;
+        writeln!(f)?;
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Last delivery time (unix seconds) keyed by alert identity
+fn sent_filename() -> PathBuf {
+    data_folder().join("alerts-sent.json")
+}
+
+fn sent() -> HashMap<String, i64> {
+    fs::read_to_string(sent_filename())
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn is_duplicate(config: &Config, alert: &Alert) -> bool {
+    sent()
+        .get(&alert.key())
+        .is_some_and(|time| alert.time.timestamp() - time < config.dedup as i64)
+}
+
+fn mark_sent(config: &Config, alert: &Alert) -> Result<()> {
+    let now = alert.time.timestamp();
+    let mut sent = sent();
+    sent.retain(|_, time| now - *time < config.dedup as i64);
+    sent.insert(alert.key(), now);
+    fs::write(sent_filename(), serde_json::to_string(&sent)?)?;
+    Ok(())
+}
+
+fn send_webhook(url: &str, alert: &Alert) -> Result<()> {
+    reqwest::blocking::Client::builder()
+        .timeout(TIMEOUT)
+        .build()?
+        .post(url)
+        .json(alert)
+        .send()?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// Read an SMTP reply (skipping continuation lines) and check its code
+fn smtp_reply(reader: &mut BufReader<TcpStream>, expected: &[u16]) -> Result<()> {
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(Error::custom("SMTP connection closed"));
+        }
+        if line.as_bytes().get(3) == Some(&b'-') {
+            continue;
+        }
+        return match line.get(..3).and_then(|code| code.parse::<u16>().ok()) {
+            Some(code) if expected.contains(&code) => Ok(()),
+            _ => Err(Error::custom(format!("SMTP error: {}", line.trim()))),
+        };
+    }
+}
+
+fn smtp_command(
+    reader: &mut BufReader<TcpStream>,
+    writer: &mut TcpStream,
+    command: &str,
+    expected: &[u16],
+) -> Result<()> {
+    write!(writer, "{command}\r\n")?;
+    smtp_reply(reader, expected)
+}
+
+fn send_email(smtp: &Smtp, alert: &Alert) -> Result<()> {
+    let addr = (smtp.host.as_str(), smtp.port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| Error::custom(format!("Unable to resolve `{}`", smtp.host)))?;
+    let stream = TcpStream::connect_timeout(&addr, TIMEOUT)?;
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    stream.set_write_timeout(Some(TIMEOUT))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+
+    smtp_reply(&mut reader, &[220])?;
+    smtp_command(
+        &mut reader,
+        &mut writer,
+        &format!("EHLO {}", alert.host),
+        &[250],
+    )?;
+    smtp_command(
+        &mut reader,
+        &mut writer,
+        &format!("MAIL FROM:<{}>", smtp.from),
+        &[250],
+    )?;
+    for to in smtp.to.iter() {
+        smtp_command(
+            &mut reader,
+            &mut writer,
+            &format!("RCPT TO:<{to}>"),
+            &[250, 251],
+        )?;
+    }
+    smtp_command(&mut reader, &mut writer, "DATA", &[354])?;
+
+    let mut message = vec![
+        format!("From: {}", smtp.from),
+        format!("To: {}", smtp.to.join(", ")),
+        format!("Subject: {}", alert.summary()),
+        format!("Date: {}", alert.time.to_rfc2822()),
+        "Content-Type: text/plain; charset=utf-8".to_string(),
+        String::new(),
+    ];
+    // dot-stuffing of body lines
+    message.extend(alert.to_string().lines().map(|line| {
+        if line.starts_with('.') {
+            format!(".{line}")
+        } else {
+            line.to_string()
+        }
+    }));
+    message.push(".".to_string());
+    smtp_command(&mut reader, &mut writer, &message.join("\r\n"), &[250])?;
+    smtp_command(&mut reader, &mut writer, "QUIT", &[221]).ok();
+    Ok(())
+}
+
+fn run_script(script: &str, alert: &Alert) -> Result<()> {
+    cmd!(script)
+        .env(
+            "KHOST_ALERT_KIND",
+            serde_json::to_value(alert.kind)?
+                .as_str()
+                .unwrap_or_default(),
+        )
+        .env("KHOST_ALERT_SUBJECT", &alert.subject)
+        .env("KHOST_ALERT_MESSAGE", &alert.message)
+        .env("KHOST_ALERT_HOST", &alert.host)
+        .env("KHOST_ALERT_TIME", alert.time.to_rfc3339())
+        .stdin_bytes(serde_json::to_vec(alert)?)
+        .run()
+}
+
+/// Deliver to every configured sink, reporting all failures
+pub fn deliver(config: &Config, alert: &Alert) -> Result<()> {
+    let mut errors = Vec::new();
+    if let Some(url) = config.webhook.as_ref() {
+        if let Err(err) = send_webhook(url, alert) {
+            errors.push(format!("webhook: {err}"));
+        }
+    }
+    if let Some(smtp) = config.smtp.as_ref() {
+        if let Err(err) = send_email(smtp, alert) {
+            errors.push(format!("smtp: {err}"));
+        }
+    }
+    if let Some(script) = config.script.as_ref() {
+        if let Err(err) = run_script(script, alert) {
+            errors.push(format!("script: {err}"));
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::custom(format!(
+            "Unable to deliver alert: {}",
+            errors.join("; ")
+        )))
+    }
+}
+
+/// Send an alert subject to quiet hours and de-duplication; never fails
+pub fn notify(ctx: &Context, alert: Alert) {
+    let config = &ctx.config.alerts;
+    if !config.enabled() {
+        return;
+    }
+    if config
+        .quiet_hours
+        .is_some_and(|quiet| quiet.contains(alert.time.hour()))
+    {
+        return;
+    }
+    if is_duplicate(config, &alert) {
+        return;
+    }
+
+    match deliver(config, &alert) {
+        Ok(()) => {
+            mark_sent(config, &alert).ok();
+        }
+        Err(err) => {
+            log::warning(err).ok();
+        }
+    }
+}
+
+/// Run `f`, sending an alert if it fails
+pub fn on_error<T, S, F>(ctx: &Context, kind: Kind, subject: S, f: F) -> Result<T>
+where
+    S: Display,
+    F: FnOnce() -> Result<T>,
+{
+    let result = f();
+    if let Err(err) = &result {
+        notify(ctx, Alert::new(kind, subject, err));
+    }
+    result
+}
+
+/// Handle `OnFailure=` of a monitored unit (`khost alert unit-failed <unit>`)
+pub fn unit_failed(ctx: &Context, unit: &str) -> Result<()> {
+    let journal = cmd!("journalctl", "-u", unit, "-n", "20", "--no-pager")
+        .unchecked()
+        .read()
+        .unwrap_or_default();
+    let message = format!("Unit `{unit}` entered the failed state.\n\n{journal}");
+    notify(ctx, Alert::new(Kind::UnitFailed, unit, message));
+    Ok(())
+}
+
+/// Periodic certificate expiry and disk space checks (`khost alert check`)
+pub fn check(ctx: &Context) -> Result<()> {
+    if let Some(certs) = ctx.config.nginx.certs() {
+        match tls::CertsStatus::try_new(&certs) {
+            Ok(status) => {
+                let leaf = status.leaf();
+                if leaf.days_remaining() < tls::EXPIRY_WARNING_DAYS {
+                    let message = format!(
+                        "Certificate `{}` ({}) expires on {} ({} days remaining)",
+                        leaf.subject,
+                        certs.crt,
+                        leaf.not_after.format("%Y-%m-%d"),
+                        leaf.days_remaining()
+                    );
+                    notify(ctx, Alert::new(Kind::CertExpiring, &leaf.subject, message));
+                }
+            }
+            Err(err) => {
+                let message = format!("Unable to read certificate `{}`: {err}", certs.crt);
+                notify(ctx, Alert::new(Kind::CertExpiring, &certs.crt, message));
+            }
+        }
+    }
+
+    let mut mounts = HashSet::new();
+    for (_, path) in storage::data_folders(ctx) {
+        let Some(filesystem) = system::filesystem(&path) else {
+            continue;
+        };
+        if filesystem.total == 0 || !mounts.insert(filesystem.mount.clone()) {
+            continue;
+        }
+        let available = filesystem.available as f64 / filesystem.total as f64 * 100.0;
+        if available < ctx.config.alerts.disk_threshold as f64 {
+            let message = format!(
+                "{} available of {} ({available:1.0}%)",
+                as_gb(filesystem.available as f64, false, false),
+                as_gb(filesystem.total as f64, false, false)
+            );
+            notify(ctx, Alert::new(Kind::LowDisk, &filesystem.mount, message));
+        }
+    }
+
+    Ok(())
+}
+
+/// Deliver a test alert to all sinks, bypassing quiet hours and de-duplication
+pub fn test(ctx: &Context) -> Result<()> {
+    let alert = Alert::new(Kind::Test, "alert delivery", "kHOST alert delivery test");
+    deliver(&ctx.config.alerts, &alert)
+}
+
+struct AlertUnit {
+    user: String,
+    binary: String,
+}
+
+impl Display for AlertUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[Unit]")?;
+        writeln!(f, "Description=kHOST alert for %i")?;
+        writeln!(f)?;
+        writeln!(f, "[Service]")?;
+        writeln!(f, "Type=oneshot")?;
+        writeln!(f, "User={}", self.user)?;
+        writeln!(f, "ExecStart={} alert unit-failed %i", self.binary)?;
+        Ok(())
+    }
+}
+
+struct CheckUnit {
+    user: String,
+    binary: String,
+}
+
+impl Display for CheckUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[Unit]")?;
+        writeln!(f, "Description=kHOST alert checks")?;
+        writeln!(f)?;
+        writeln!(f, "[Service]")?;
+        writeln!(f, "Type=oneshot")?;
+        writeln!(f, "User={}", self.user)?;
+        writeln!(f, "ExecStart={} alert check", self.binary)?;
+        Ok(())
+    }
+}
+
+struct CheckTimer;
+
+impl Display for CheckTimer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[Unit]")?;
+        writeln!(f, "Description=kHOST alert checks timer")?;
+        writeln!(f)?;
+        writeln!(f, "[Timer]")?;
+        writeln!(f, "OnBootSec=5min")?;
+        writeln!(f, "OnUnitActiveSec=1h")?;
+        writeln!(f)?;
+        writeln!(f, "[Install]")?;
+        writeln!(f, "WantedBy=timers.target")?;
+        Ok(())
+    }
+}
+
+struct Dropin;
+
+impl Display for Dropin {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[Unit]")?;
+        writeln!(f, "OnFailure={SERVICE_NAME}@%n.service")?;
+        Ok(())
+    }
+}
+
+fn template_path() -> PathBuf {
+    systemd::service_path(&format!("{SERVICE_NAME}@"))
+}
+
+fn install(ctx: &Context) -> Result<()> {
+    let binary = khost::binary()?.display().to_string();
+    let user = ctx.username.clone();
+
+    sudo::fs::write(
+        template_path(),
+        AlertUnit {
+            user: user.clone(),
+            binary: binary.clone(),
+        }
+        .to_string(),
+    )?;
+    sudo::fs::write(
+        systemd::service_path(CHECK_UNIT),
+        CheckUnit { user, binary }.to_string(),
+    )?;
+    sudo::fs::write(systemd::timer_path(CHECK_UNIT), CheckTimer.to_string())?;
+
+    for service in ctx.services() {
+        let path = systemd::dropin_path(&service.name, DROPIN);
+        if service.enabled {
+            if let Some(folder) = path.parent() {
+                sudo!("mkdir", "-p", folder).run()?;
+            }
+            sudo::fs::write(path, Dropin.to_string())?;
+        } else if path.exists() {
+            sudo::fs::remove_file(path)?;
+        }
+    }
+
+    systemd::daemon_reload()?;
+    sudo!(
+        "systemctl",
+        "enable",
+        "--now",
+        format!("{CHECK_UNIT}.timer")
+    )
+    .run()
+}
+
+fn remove(ctx: &Context) -> Result<()> {
+    sudo!(
+        "systemctl",
+        "disable",
+        "--now",
+        format!("{CHECK_UNIT}.timer")
+    )
+    .unchecked()
+    .run()?;
+    for service in ctx.services() {
+        let path = systemd::dropin_path(&service.name, DROPIN);
+        if path.exists() {
+            sudo::fs::remove_file(path)?;
+        }
+    }
+    sudo::fs::remove_file(systemd::timer_path(CHECK_UNIT))?;
+    sudo::fs::remove_file(systemd::service_path(CHECK_UNIT))?;
+    sudo::fs::remove_file(template_path())?;
+    systemd::daemon_reload()
+}
+
+/// Remove the `OnFailure=` drop-in of a service that no longer exists
+pub fn remove_dropin(service: &str) -> Result<()> {
+    let path = systemd::dropin_path(service, DROPIN);
+    if path.exists() {
+        sudo::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+pub fn reconfigure(ctx: &Context) -> Result<()> {
+    if ctx.config.alerts.enabled() {
+        step("Configuring alerts", || install(ctx))?;
+    } else if template_path().exists() {
+        step("Removing alerts", || remove(ctx))?;
+    }
+    Ok(())
+}
+
+fn ask_optional_string(prompt: &str, current: Option<&String>) -> Result<Option<String>> {
+    let mut input = cliclack::input(prompt).required(false);
+    if let Some(current) = current {
+        input = input.default_input(current);
+    }
+    let value: String = input.interact()?;
+    let value = value.trim().to_string();
+    Ok((!value.is_empty()).then_some(value))
+}
+
+fn ask_number<T: FromStr + ToString>(prompt: &str, current: T) -> Result<T> {
+    let value: String = cliclack::input(prompt)
+        .default_input(&current.to_string())
+        .validate(|input: &String| match input.parse::<T>() {
+            Ok(_) => Ok(()),
+            Err(_) => Err("Please enter a number".to_string()),
+        })
+        .interact()?;
+    value
+        .parse()
+        .map_err(|_| Error::custom(format!("Invalid number `{value}`")))
+}
+
+fn ask_smtp(current: Option<&Smtp>) -> Result<Option<Smtp>> {
+    let to = current.map(|smtp| smtp.to.join(", "));
+    let Some(to) = ask_optional_string(
+        "Email recipients, comma separated (empty to disable email):",
+        to.as_ref(),
+    )?
+    else {
+        return Ok(None);
+    };
+
+    let current = current.cloned().unwrap_or_default();
+    let host: String = cliclack::input("SMTP relay host:")
+        .default_input(&current.host)
+        .interact()?;
+    let port = ask_number("SMTP relay port:", current.port)?;
+    let from: String = cliclack::input("Sender address:")
+        .default_input(&current.from)
+        .interact()?;
+
+    Ok(Some(Smtp {
+        host: host.trim().to_string(),
+        port,
+        from: from.trim().to_string(),
+        to: to
+            .split(',')
+            .map(|to| to.trim().to_string())
+            .filter(|to| !to.is_empty())
+            .collect(),
+    }))
+}
+
+fn ask_quiet_hours(current: Option<QuietHours>) -> Result<Option<QuietHours>> {
+    let current = current.map(|quiet| quiet.to_string());
+    let value = cliclack::input("Quiet hours, e.g. 22-7 (empty for none):")
+        .required(false)
+        .default_input(&current.unwrap_or_default())
+        .validate(|input: &String| {
+            if input.trim().is_empty() || parse_quiet_hours(input).is_some() {
+                Ok(())
+            } else {
+                Err("Please enter a range of hours such as 22-7".to_string())
+            }
+        })
+        .interact::<String>()?;
+    Ok(parse_quiet_hours(&value))
+}
+
+/// Accepts `22-7` as well as `22:00-07:00`
+fn parse_quiet_hours(input: &str) -> Option<QuietHours> {
+    let hour = |value: &str| {
+        let value = value.trim();
+        let value = value.strip_suffix(":00").unwrap_or(value);
+        value.parse::<u32>().ok().filter(|hour| *hour < 24)
+    };
+    let (start, end) = input.trim().split_once('-')?;
+    let quiet = QuietHours {
+        start: hour(start)?,
+        end: hour(end)?,
+    };
+    (quiet.start != quiet.end).then_some(quiet)
+}
+
+pub fn configure(ctx: &mut Context) -> Result<()> {
+    let config = ctx.config.alerts.clone();
+
+    let webhook = ask_optional_string("Webhook URL (empty to disable):", config.webhook.as_ref())?;
+    if let Some(url) = webhook.as_ref() {
+        if reqwest::Url::parse(url).is_err() {
+            return Err(Error::custom(format!("Invalid webhook URL `{url}`")));
+        }
+    }
+    let smtp = ask_smtp(config.smtp.as_ref())?;
+    let script = ask_optional_string(
+        "Alert script path (empty to disable):",
+        config.script.as_ref(),
+    )?;
+    if let Some(script) = script.as_ref() {
+        if !Path::new(script).is_file() {
+            return Err(Error::custom(format!("Script `{script}` not found")));
+        }
+    }
+    let dedup = ask_number(
+        "Suppress repeated identical alerts for (seconds):",
+        config.dedup,
+    )?;
+    let quiet_hours = ask_quiet_hours(config.quiet_hours)?;
+    let disk_threshold = ask_number(
+        "Alert when available disk space falls below (%):",
+        config.disk_threshold,
+    )?;
+
+    ctx.config.alerts = Config {
+        webhook,
+        smtp,
+        script,
+        dedup,
+        quiet_hours,
+        disk_threshold,
+    };
+    ctx.config.save()?;
+
+    reconfigure(ctx)?;
+
+    if ctx.config.alerts.enabled()
+        && confirm("Send a test alert?")
+            .initial_value(true)
+            .interact()?
+    {
+        test(ctx)?;
+        log::success("Test alert delivered")?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn alert() -> Alert {
+        Alert {
+            kind: Kind::Test,
+            subject: "kaspad-mainnet".to_string(),
+            message: "first line\n.dotted line".to_string(),
+            host: "node1".to_string(),
+            time: Local::now(),
+        }
+    }
+
+    /// Accept one SMTP session, answering each command with the next scripted reply
+    fn smtp_stub(replies: &'static [&'static str]) -> (u16, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut received = Vec::new();
+            let mut replies = replies.iter();
+            stream
+                .write_all(replies.next().unwrap().as_bytes())
+                .unwrap();
+            let mut data = false;
+            for reply in replies {
+                loop {
+                    let mut line = String::new();
+                    if reader.read_line(&mut line).unwrap() == 0 {
+                        return received;
+                    }
+                    let line = line.trim_end().to_string();
+                    let done = !data || line == ".";
+                    data = line == "DATA" || (data && line != ".");
+                    received.push(line);
+                    if done {
+                        break;
+                    }
+                }
+                stream.write_all(reply.as_bytes()).unwrap();
+            }
+            received
+        });
+        (port, handle)
+    }
+
+    fn smtp(port: u16) -> Smtp {
+        Smtp {
+            host: "127.0.0.1".to_string(),
+            port,
+            from: "khost@node1".to_string(),
+            to: vec![
+                "ops@example.com".to_string(),
+                "oncall@example.com".to_string(),
+            ],
+        }
+    }
+
+    #[test]
+    fn sends_email() {
+        let (port, handle) = smtp_stub(&[
+            "220 stub ESMTP\r\n",
+            "250-stub\r\n250 8BITMIME\r\n",
+            "250 ok\r\n",
+            "250 ok\r\n",
+            "251 forwarded\r\n",
+            "354 go ahead\r\n",
+            "250 queued\r\n",
+            "221 bye\r\n",
+        ]);
+        send_email(&smtp(port), &alert()).unwrap();
+        let received = handle.join().unwrap();
+
+        assert_eq!(received[0], "EHLO node1");
+        assert_eq!(received[1], "MAIL FROM:<khost@node1>");
+        assert_eq!(received[2], "RCPT TO:<ops@example.com>");
+        assert_eq!(received[3], "RCPT TO:<oncall@example.com>");
+        assert_eq!(received[4], "DATA");
+        assert!(received.contains(&"To: ops@example.com, oncall@example.com".to_string()));
+        assert!(received.contains(&"..dotted line".to_string()));
+        assert_eq!(received.last().unwrap(), "QUIT");
+    }
+
+    #[test]
+    fn reports_rejected_recipient() {
+        let (port, handle) = smtp_stub(&[
+            "220 stub ESMTP\r\n",
+            "250 stub\r\n",
+            "250 ok\r\n",
+            "550 no such user\r\n",
+        ]);
+        let err = send_email(&smtp(port), &alert()).unwrap_err().to_string();
+        assert!(err.contains("550 no such user"), "{err}");
+        drop(handle);
+    }
+
+    #[test]
+    fn reports_closed_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = thread::spawn(move || drop(listener.accept().unwrap()));
+        let err = send_email(&smtp(port), &alert()).unwrap_err().to_string();
+        assert!(err.contains("closed"), "{err}");
+        handle.join().unwrap();
+    }
+
+    /// Accept one HTTP request and answer with `status`; returns the request body
+    fn webhook_stub(status: &'static str) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/alert", listener.local_addr().unwrap());
+        let handle = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+            )
+            .unwrap();
+            String::from_utf8(body).unwrap()
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn posts_webhook() {
+        let (url, handle) = webhook_stub("200 OK");
+        send_webhook(&url, &alert()).unwrap();
+        let body: serde_json::Value = serde_json::from_str(&handle.join().unwrap()).unwrap();
+        assert_eq!(body["subject"], "kaspad-mainnet");
+        assert_eq!(body["host"], "node1");
+    }
+
+    #[test]
+    fn reports_webhook_error_status() {
+        let (url, handle) = webhook_stub("500 Internal Server Error");
+        let err = send_webhook(&url, &alert()).unwrap_err().to_string();
+        assert!(err.contains("500"), "{err}");
+        handle.join().unwrap();
+    }
+}
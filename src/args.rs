@@ -1,5 +1,5 @@
 use crate::imports::*;
-pub use clap::Parser;
+pub use clap::{Parser, Subcommand};
 
 #[derive(Default, Parser, Debug)]
 #[command(version, about, long_about = None)]
@@ -10,6 +10,44 @@ pub struct Args {
     /// Reset configuration
     #[arg(short, long, default_value = "false")]
     pub reset: bool,
+    /// Query node health from a wRPC (json) address (e.g. 127.0.0.1:18110)
+    #[arg(long, value_name = "ADDRESS")]
+    pub health: Option<std::net::SocketAddr>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    /// Send alerts (run by the `khost-alert` units)
+    Alert {
+        #[command(subcommand)]
+        action: AlertAction,
+    },
+    /// Enforce the monthly transfer budget (run by the `khost-bandwidth` timer)
+    Bandwidth,
+    /// Serve Prometheus metrics (run by the `khost-exporter` service)
+    Exporter {
+        /// Listen address
+        #[arg(long, default_value_t = exporter::listen_address(&Default::default()))]
+        listen: std::net::SocketAddr,
+    },
+    /// Record data folder sizes and free space (run by the `khost-storage` timer)
+    Storage,
+    /// Full-screen live dashboard
+    Top,
+    /// Restart stalled nodes (run by the `khost-watchdog` service)
+    Watchdog,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum AlertAction {
+    /// Report a failed systemd unit
+    UnitFailed { unit: String },
+    /// Check certificate expiry and disk space
+    Check,
+    /// Deliver a test alert to all configured sinks
+    Test,
 }
 
 pub fn parse() -> Args {
@@ -25,5 +63,16 @@ pub fn parse() -> Args {
         std::process::exit(0);
     }
 
+    if let Some(addr) = args.health {
+        match health::fetch(addr, health::TIMEOUT) {
+            Ok(health) => println!("{health}"),
+            Err(err) => {
+                println!("{err}");
+                std::process::exit(1);
+            }
+        }
+        std::process::exit(0);
+    }
+
     args
 }
@@ -0,0 +1,645 @@
+use crate::imports::*;
+use chrono::{Datelike, Local, NaiveDate, Timelike};
+
+/// Bandwidth units are SI (1 GB = 10^9 bytes) as used by hosting providers
+const GB: u64 = 1_000_000_000;
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct Config {
+    /// Interface used for budget accounting (all interfaces if not set)
+    #[serde(default)]
+    pub interface: Option<String>,
+    /// Monthly transfer budget in GB (received + transmitted)
+    #[serde(default)]
+    pub budget: Option<u64>,
+    /// Lower kaspad incoming peer limit to this value while the budget is exceeded
+    #[serde(default)]
+    pub throttle_peers: Option<u16>,
+    #[serde(default)]
+    pub throttled: Option<Throttle>,
+}
+
+/// Active incoming peer throttle
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Throttle {
+    /// Month (`YYYY-MM`) the throttle was applied in
+    pub month: String,
+    /// Incoming peer limit applied to the nodes
+    #[serde(default)]
+    pub limit: u16,
+}
+
+// vnstat --json (json version 2, vnStat 2.x)
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VnStat {
+    pub jsonversion: String,
+    pub interfaces: Vec<InterfaceTraffic>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InterfaceTraffic {
+    pub name: String,
+    pub traffic: Traffic,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Traffic {
+    #[serde(default, alias = "hours")]
+    pub hour: Vec<Entry>,
+    #[serde(default, alias = "days")]
+    pub day: Vec<Entry>,
+    #[serde(default, alias = "months")]
+    pub month: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EntryDate {
+    pub year: i32,
+    pub month: u32,
+    #[serde(default)]
+    pub day: Option<u32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EntryTime {
+    pub hour: u32,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Entry {
+    pub date: EntryDate,
+    #[serde(default)]
+    pub time: Option<EntryTime>,
+    /// json version 1 stores the hour in the entry itself
+    #[serde(default, rename = "id")]
+    id: Option<u32>,
+    pub rx: u64,
+    pub tx: u64,
+}
+
+impl Entry {
+    pub fn hour(&self) -> Option<u32> {
+        self.time.as_ref().map(|time| time.hour).or(self.id)
+    }
+
+    fn is_month(&self, year: i32, month: u32) -> bool {
+        self.date.year == year && self.date.month == month
+    }
+
+    fn is_day(&self, date: NaiveDate) -> bool {
+        self.is_month(date.year(), date.month()) && self.date.day == Some(date.day())
+    }
+}
+
+impl VnStat {
+    pub fn parse(json: &str) -> Result<Self> {
+        let mut vnstat: VnStat = serde_json::from_str(json)?;
+        // json version 1 (vnStat 1.x) reports KiB
+        if vnstat.jsonversion == "1" {
+            for interface in vnstat.interfaces.iter_mut() {
+                let traffic = &mut interface.traffic;
+                for entry in traffic
+                    .hour
+                    .iter_mut()
+                    .chain(traffic.day.iter_mut())
+                    .chain(traffic.month.iter_mut())
+                {
+                    entry.rx *= 1024;
+                    entry.tx *= 1024;
+                }
+            }
+        }
+        Ok(vnstat)
+    }
+
+    pub fn read() -> Result<Self> {
+        Self::parse(&cmd!("vnstat", "--json").read()?)
+    }
+
+    /// Interfaces selected for budget accounting
+    pub fn interfaces<'a>(
+        &'a self,
+        config: &'a Config,
+    ) -> impl Iterator<Item = &'a InterfaceTraffic> + 'a {
+        self.interfaces.iter().filter(|interface| {
+            config
+                .interface
+                .as_ref()
+                .is_none_or(|name| *name == interface.name)
+        })
+    }
+}
+
+/// Totals for a single interface relative to the current local time
+#[derive(Debug, Clone, Default)]
+pub struct Summary {
+    pub hour: (u64, u64),
+    pub last_24h: (u64, u64),
+    pub today: (u64, u64),
+    pub yesterday: (u64, u64),
+    pub month: (u64, u64),
+    pub last_month: (u64, u64),
+}
+
+impl Summary {
+    pub fn new(traffic: &Traffic) -> Self {
+        let now = Local::now();
+        let today = now.date_naive();
+        let yesterday = today.pred_opt().unwrap_or(today);
+        let (last_month_year, last_month) = if today.month() == 1 {
+            (today.year() - 1, 12)
+        } else {
+            (today.year(), today.month() - 1)
+        };
+
+        let sum = |entries: &mut dyn Iterator<Item = &Entry>| {
+            entries.fold((0, 0), |(rx, tx), entry| (rx + entry.rx, tx + entry.tx))
+        };
+
+        let hours = traffic
+            .hour
+            .iter()
+            .filter(|entry| {
+                (entry.is_day(today) && entry.hour().is_some_and(|h| h <= now.hour()))
+                    || (entry.is_day(yesterday) && entry.hour().is_some_and(|h| h > now.hour()))
+            })
+            .collect::<Vec<_>>();
+
+        Self {
+            hour: sum(&mut hours
+                .iter()
+                .copied()
+                .filter(|entry| entry.is_day(today) && entry.hour() == Some(now.hour()))),
+            last_24h: sum(&mut hours.iter().copied()),
+            today: sum(&mut traffic.day.iter().filter(|entry| entry.is_day(today))),
+            yesterday: sum(&mut traffic.day.iter().filter(|entry| entry.is_day(yesterday))),
+            month: sum(&mut traffic
+                .month
+                .iter()
+                .filter(|entry| entry.is_month(today.year(), today.month()))),
+            last_month: sum(&mut traffic
+                .month
+                .iter()
+                .filter(|entry| entry.is_month(last_month_year, last_month))),
+        }
+    }
+
+    pub fn month_total(&self) -> u64 {
+        self.month.0 + self.month.1
+    }
+
+    /// Linear projection of the current month transfer to the end of the month
+    pub fn projection(&self) -> u64 {
+        (self.month_total() as f64 / month_elapsed()) as u64
+    }
+}
+
+/// Fraction of the current month elapsed (local time)
+pub fn month_elapsed() -> f64 {
+    let now = Local::now().naive_local();
+    let start = NaiveDate::from_ymd_opt(now.year(), now.month(), 1).unwrap();
+    let end = if now.month() == 12 {
+        NaiveDate::from_ymd_opt(now.year() + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(now.year(), now.month() + 1, 1)
+    }
+    .unwrap();
+    let total = (end - start).num_seconds() as f64;
+    let elapsed = (now - start.and_hms_opt(0, 0, 0).unwrap()).num_seconds() as f64;
+    (elapsed / total).max(1.0 / total)
+}
+
+pub fn month_key() -> String {
+    Local::now().format("%Y-%m").to_string()
+}
+
+fn fmt_traffic((rx, tx): (u64, u64)) -> String {
+    format!(
+        "{} (rx {} / tx {})",
+        as_data_size((rx + tx) as f64, true),
+        as_data_size(rx as f64, true),
+        as_data_size(tx as f64, true)
+    )
+}
+
+/// Budget usage across the interfaces selected for accounting
+#[derive(Debug, Clone)]
+pub struct Budget {
+    pub budget: u64,
+    pub used: u64,
+    pub projected: u64,
+}
+
+impl Budget {
+    pub fn is_exceeded(&self) -> bool {
+        self.used > self.budget
+    }
+
+    pub fn is_projected_to_exceed(&self) -> bool {
+        self.projected > self.budget
+    }
+}
+
+impl Display for Budget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} of {} used ({:.0}%), {} projected",
+            as_data_size(self.used as f64, true),
+            as_data_size(self.budget as f64, true),
+            self.used as f64 / self.budget as f64 * 100.0,
+            as_data_size(self.projected as f64, true)
+        )
+    }
+}
+
+pub fn budget(config: &Config, vnstat: &VnStat) -> Option<Budget> {
+    // a zero budget is treated as no budget
+    let budget = config.budget.filter(|budget| *budget > 0)? * GB;
+    let (used, projected) = vnstat
+        .interfaces(config)
+        .map(|interface| Summary::new(&interface.traffic))
+        .fold((0, 0), |(used, projected), summary| {
+            (
+                used + summary.month_total(),
+                projected + summary.projection(),
+            )
+        });
+    Some(Budget {
+        budget,
+        used,
+        projected,
+    })
+}
+
+pub struct Report<'a> {
+    pub vnstat: &'a VnStat,
+    pub config: &'a Config,
+}
+
+impl Display for Report<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut rows = Vec::new();
+        for interface in self.vnstat.interfaces.iter() {
+            let summary = Summary::new(&interface.traffic);
+            if !rows.is_empty() {
+                rows.push(Content::separator());
+            }
+            rows.push(Content::field(
+                "Interface:",
+                style(&interface.name).cyan().bright(),
+            ));
+            rows.push(Content::field("This hour:", fmt_traffic(summary.hour)));
+            rows.push(Content::field(
+                "Last 24 hours:",
+                fmt_traffic(summary.last_24h),
+            ));
+            rows.push(Content::field("Today:", fmt_traffic(summary.today)));
+            rows.push(Content::field("Yesterday:", fmt_traffic(summary.yesterday)));
+            rows.push(Content::field("This month:", fmt_traffic(summary.month)));
+            rows.push(Content::field(
+                "Last month:",
+                fmt_traffic(summary.last_month),
+            ));
+            rows.push(Content::field(
+                "Projected:",
+                as_data_size(summary.projection() as f64, true),
+            ));
+        }
+
+        if let Some(budget) = budget(self.config, self.vnstat) {
+            rows.push(Content::separator());
+            let text = budget.to_string();
+            let text = if budget.is_exceeded() {
+                style(text).red().bright()
+            } else if budget.is_projected_to_exceed() {
+                style(text).yellow().bright()
+            } else {
+                style(text).green().bright()
+            };
+            rows.push(Content::field("Monthly budget:", text));
+        }
+
+        write!(f, "{}", content(rows))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Period {
+    Hourly,
+    Daily,
+    Monthly,
+}
+
+/// Per-period totals for each interface
+pub struct Table<'a> {
+    pub vnstat: &'a VnStat,
+    pub period: Period,
+}
+
+impl Display for Table<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut rows = Vec::new();
+        for interface in self.vnstat.interfaces.iter() {
+            if !rows.is_empty() {
+                rows.push(Content::separator());
+            }
+            rows.push(Content::field(
+                "Interface:",
+                style(&interface.name).cyan().bright(),
+            ));
+            let (entries, count) = match self.period {
+                Period::Hourly => (&interface.traffic.hour, 24),
+                Period::Daily => (&interface.traffic.day, 30),
+                Period::Monthly => (&interface.traffic.month, 12),
+            };
+            let skip = entries.len().saturating_sub(count);
+            for entry in entries.iter().skip(skip) {
+                let date = &entry.date;
+                let label = match self.period {
+                    Period::Hourly => format!(
+                        "{:04}-{:02}-{:02} {:02}:00",
+                        date.year,
+                        date.month,
+                        date.day.unwrap_or_default(),
+                        entry.hour().unwrap_or_default()
+                    ),
+                    Period::Daily => format!(
+                        "{:04}-{:02}-{:02}",
+                        date.year,
+                        date.month,
+                        date.day.unwrap_or_default()
+                    ),
+                    Period::Monthly => format!("{:04}-{:02}", date.year, date.month),
+                };
+                rows.push(Content::field(label, fmt_traffic((entry.rx, entry.tx))));
+            }
+        }
+        write!(f, "{}", content(rows))
+    }
+}
+
+/// Warnings for the monthly transfer budget
+pub fn conflicts(ctx: &Context) -> Vec<String> {
+    if ctx.config.bandwidth.budget.is_none() {
+        return vec![];
+    }
+    match VnStat::read() {
+        Ok(vnstat) => match budget(&ctx.config.bandwidth, &vnstat) {
+            Some(budget) if budget.is_exceeded() => {
+                vec![format!("Monthly transfer budget exceeded: {budget}")]
+            }
+            Some(budget) if budget.is_projected_to_exceed() => {
+                vec![format!(
+                    "Monthly transfer budget projected to be exceeded: {budget}"
+                )]
+            }
+            _ => vec![],
+        },
+        Err(err) => vec![format!("Unable to read vnstat data: {err}")],
+    }
+}
+
+/// Environment file overriding the node incoming peer limit (`kaspad::PEER_ARGS`)
+pub fn throttle_filename(service: &str) -> PathBuf {
+    data_folder()
+        .join("throttle")
+        .join(format!("{service}.env"))
+}
+
+/// Write (or remove) node environment files for the current throttle
+fn apply_throttle(ctx: &Context) -> Result<()> {
+    for config in ctx.config.kaspad.iter() {
+        let path = throttle_filename(&config.service_name());
+        match &ctx.config.bandwidth.throttled {
+            Some(throttle) => {
+                let limit = config
+                    .max_incoming_peers()
+                    .map_or(throttle.limit, |peers| peers.min(throttle.limit));
+                fs::create_dir_all(path.parent().unwrap())?;
+                fs::write(
+                    path,
+                    format!("{}={}\n", kaspad::PEER_ARGS, kaspad::peer_args(Some(limit))),
+                )?;
+            }
+            None => {
+                if path.exists() {
+                    fs::remove_file(path)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Lower kaspad incoming peer limits while the monthly budget is exceeded
+/// and restore them once a new month begins. Returns `true` if the nodes
+/// need to be restarted to apply the change.
+pub fn enforce(ctx: &mut Context) -> Result<bool> {
+    let config = &ctx.config.bandwidth;
+    if config.budget.is_none() && config.throttled.is_none() {
+        return Ok(false);
+    }
+
+    let exceeded = VnStat::read()
+        .ok()
+        .and_then(|vnstat| budget(config, &vnstat))
+        .is_some_and(|budget| budget.is_exceeded());
+    let month = month_key();
+
+    match (config.throttled.as_ref(), config.throttle_peers) {
+        (None, Some(limit)) if exceeded => {
+            ctx.config.bandwidth.throttled = Some(Throttle { month, limit });
+            ctx.config.save()?;
+            log::warning(format!(
+                "Monthly transfer budget exceeded, limiting incoming peers to {limit}"
+            ))?;
+        }
+        (Some(throttle), limit)
+            if throttle.month != month || config.budget.is_none() || limit.is_none() =>
+        {
+            ctx.config.bandwidth.throttled = None;
+            ctx.config.save()?;
+            log::info("Restoring incoming peer limits")?;
+        }
+        _ => return Ok(false),
+    }
+
+    apply_throttle(ctx)?;
+    Ok(true)
+}
+
+/// Enforce the budget without user interaction (`khost bandwidth`)
+pub fn run(ctx: &mut Context) -> Result<()> {
+    if enforce(ctx)? {
+        for config in kaspad::active_configs(ctx) {
+            watchdog::restart(&config.service_name())?;
+        }
+    }
+    Ok(())
+}
+
+/// Periodic budget enforcement unit (`khost bandwidth`)
+const UNIT_NAME: &str = "khost-bandwidth";
+const SUDOERS_PATH: &str = "/etc/sudoers.d/khost-bandwidth";
+
+struct BandwidthUnit {
+    user: String,
+    binary: String,
+}
+
+impl Display for BandwidthUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[Unit]")?;
+        writeln!(f, "Description=kHOST monthly transfer budget enforcement")?;
+        writeln!(f)?;
+        writeln!(f, "[Service]")?;
+        writeln!(f, "Type=oneshot")?;
+        writeln!(f, "User={}", self.user)?;
+        writeln!(f, "ExecStart={} bandwidth", self.binary)?;
+        Ok(())
+    }
+}
+
+struct BandwidthTimer;
+
+impl Display for BandwidthTimer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[Unit]")?;
+        writeln!(
+            f,
+            "Description=kHOST monthly transfer budget enforcement timer"
+        )?;
+        writeln!(f)?;
+        writeln!(f, "[Timer]")?;
+        writeln!(f, "OnBootSec=5min")?;
+        writeln!(f, "OnUnitActiveSec=15min")?;
+        writeln!(f)?;
+        writeln!(f, "[Install]")?;
+        writeln!(f, "WantedBy=timers.target")?;
+        Ok(())
+    }
+}
+
+/// Install the enforcement timer while a budget with a peer throttle is
+/// configured, remove it otherwise
+pub fn reconfigure(ctx: &Context) -> Result<()> {
+    let config = &ctx.config.bandwidth;
+    let timer = systemd::timer_path(UNIT_NAME);
+    if config.budget.is_some() && config.throttle_peers.is_some() {
+        step("Configuring bandwidth budget enforcement", || {
+            watchdog::install_sudoers(ctx, SUDOERS_PATH, "kHOST bandwidth budget")?;
+            let unit = BandwidthUnit {
+                user: ctx.username.clone(),
+                binary: khost::binary()?.display().to_string(),
+            };
+            sudo::fs::write(systemd::service_path(UNIT_NAME), unit.to_string())?;
+            sudo::fs::write(&timer, BandwidthTimer.to_string())?;
+            systemd::daemon_reload()?;
+            sudo!("systemctl", "enable", "--now", format!("{UNIT_NAME}.timer")).run()
+        })?;
+    } else if timer.exists() {
+        step("Removing bandwidth budget enforcement", || {
+            sudo!(
+                "systemctl",
+                "disable",
+                "--now",
+                format!("{UNIT_NAME}.timer")
+            )
+            .run()?;
+            sudo::fs::remove_file(&timer)?;
+            sudo::fs::remove_file(systemd::service_path(UNIT_NAME))?;
+            sudo::fs::remove_file(SUDOERS_PATH)?;
+            systemd::daemon_reload()
+        })?;
+    }
+    Ok(())
+}
+
+fn ask_optional<T: FromStr + ToString>(prompt: &str, current: Option<T>) -> Result<Option<T>> {
+    let input: String = cliclack::input(prompt)
+        .required(false)
+        .default_input(&current.map(|v| v.to_string()).unwrap_or_default())
+        .validate(|input: &String| {
+            if input.is_empty() || input.parse::<T>().is_ok() {
+                Ok(())
+            } else {
+                Err("Please enter a number (empty to disable)".to_string())
+            }
+        })
+        .interact()?;
+    Ok(input.parse::<T>().ok())
+}
+
+pub fn configure(ctx: &mut Context) -> Result<()> {
+    let vnstat = VnStat::read()?;
+
+    let mut selector = cliclack::select("Interface used for budget accounting")
+        .item(None, "All interfaces", "")
+        .initial_value(ctx.config.bandwidth.interface.clone());
+    for interface in vnstat.interfaces.iter() {
+        selector = selector.item(Some(interface.name.clone()), &interface.name, "");
+    }
+    let interface = selector.interact()?;
+
+    let budget = ask_optional(
+        "Monthly transfer budget in GB (empty or 0 to disable):",
+        ctx.config.bandwidth.budget,
+    )?
+    .filter(|budget| *budget > 0);
+    let throttle_peers = if budget.is_some() {
+        ask_optional(
+            "Limit incoming peers when the budget is exceeded (empty to disable):",
+            ctx.config.bandwidth.throttle_peers,
+        )?
+    } else {
+        None
+    };
+
+    let config = &mut ctx.config.bandwidth;
+    config.interface = interface;
+    config.budget = budget;
+    config.throttle_peers = throttle_peers;
+    ctx.config.save()?;
+
+    reconfigure(ctx)?;
+    if enforce(ctx)? {
+        kaspad::restart_all(ctx)?;
+    }
+    Ok(())
+}
+
+pub fn report(ctx: &mut Context) -> Result<()> {
+    let report = cliclack::select("Bandwidth usage")
+        .item(None, "Summary", "totals, projection and budget")
+        .item(Some(Period::Hourly), "Hourly", "last 24 hours")
+        .item(Some(Period::Daily), "Daily", "last 30 days")
+        .item(Some(Period::Monthly), "Monthly", "last 12 months")
+        .interact()?;
+
+    let vnstat = VnStat::read()?;
+    match report {
+        None => {
+            let config = &ctx.config.bandwidth;
+            log::info(Report {
+                vnstat: &vnstat,
+                config,
+            })?;
+            if confirm("Configure monthly transfer budget?")
+                .initial_value(false)
+                .interact()?
+            {
+                configure(ctx)?;
+            }
+        }
+        Some(period) => {
+            log::info(Table {
+                vnstat: &vnstat,
+                period,
+            })?;
+        }
+    }
+
+    Ok(())
+}
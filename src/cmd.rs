@@ -74,6 +74,14 @@ impl Expression {
         self
     }
 
+    pub fn env<K, V>(&self, name: K, val: V) -> Self
+    where
+        K: Into<OsString>,
+        V: Into<OsString>,
+    {
+        Self(self.0.env(name, val))
+    }
+
     pub fn unchecked(&self) -> Self {
         Self(self.0.unchecked())
     }
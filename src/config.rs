@@ -1,6 +1,5 @@
 use crate::imports::*;
-
-const CONFIG_VERSION: u64 = 2;
+use migration::CONFIG_VERSION;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -12,8 +11,18 @@ pub struct Config {
     pub fqdn: Option<Vec<String>>,
     pub ip: Option<String>,
     pub nginx: nginx::Config,
+    #[serde(default)]
+    pub acme: acme::Config,
+    #[serde(default)]
+    pub alerts: alerts::Config,
+    #[serde(default)]
+    pub bandwidth: bandwidth::Config,
     pub kaspad: Vec<kaspad::Config>,
     pub resolver: resolver::Config,
+    #[serde(default)]
+    pub exporter: exporter::Config,
+    #[serde(default)]
+    pub watchdog: watchdog::Config,
 }
 
 impl Config {
@@ -38,53 +47,71 @@ impl Config {
             fqdn: None,
             ip: None,
             nginx,
+            acme: acme::Config::default(),
+            alerts: alerts::Config::default(),
+            bandwidth: bandwidth::Config::default(),
             kaspad,
             resolver,
+            exporter: exporter::Config::default(),
+            watchdog: watchdog::Config::default(),
         })
     }
 }
 
 impl Config {
+    pub fn filename() -> PathBuf {
+        data_folder().join("config.json")
+    }
+
+    pub fn exists() -> bool {
+        Self::filename().exists()
+    }
+
     pub fn load() -> Result<Self> {
-        let config_path = data_folder().join("config.json");
+        let config_path = Self::filename();
         if !config_path.exists() {
             return Err(Error::custom("Config file not found"));
         }
-        let mut config: Config = serde_json::from_str(&fs::read_to_string(config_path)?)?;
-
-        let mut update = false;
-        // Migrate old config
-        if config.version == 1 {
-            config.kaspad.iter_mut().for_each(|config| {
-                if let Some(branch) = config.origin_mut().branch_mut() {
-                    if branch == "omega" {
-                        *branch = "pnn-v1".to_string();
-                        update = true;
-                    }
-                }
-            });
-        }
-
-        if update {
+        let mut config: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&config_path)?)?;
+
+        let version = migration::version(&config);
+        let original = config.clone();
+        let applied = migration::migrate(&mut config)?;
+        let config: Config = serde_json::from_value(config)?;
+
+        if !applied.is_empty() {
+            let backup = migration::backup_filename(&config_path, version);
+            fs::copy(&config_path, &backup)?;
+            // keep the pre-migration config restorable from the history
+            history::record(original, &format!("Config v{version} (before migration)"))?;
+            history::record(serde_json::to_value(&config)?, "Config migration")?;
+            config.save()?;
             log::success(format!(
-                "Updated kHOST config to version {}",
-                CONFIG_VERSION
+                "Updated kHOST config to version {CONFIG_VERSION} (backup: {})\n{}",
+                backup.display(),
+                applied.join("\n")
             ))?;
-            config.version = CONFIG_VERSION;
-            config.save()?;
         }
 
         Ok(config)
     }
 
     pub fn save(&self) -> Result<()> {
-        let config_path = data_folder().join("config.json");
-        fs::write(config_path, serde_json::to_string_pretty(&self)?)?;
+        fs::write(Self::filename(), serde_json::to_string_pretty(&self)?)?;
+        if let Err(err) = history::record_save(self) {
+            log::warning(format!("Unable to record config history: {err}")).ok();
+        }
         Ok(())
     }
 
     pub fn reset() {
-        let path = data_folder().join("config.json");
+        let path = Self::filename();
+        if path.exists() {
+            if let Err(err) = history::record_reset() {
+                let _ = log::warning(format!("Unable to record config history: {err}"));
+            }
+        }
         if let Err(err) = fs::remove_file(&path) {
             let _ = log::error(format!(
                 "Failed to reset config file: {}\n{err}",
@@ -97,13 +124,9 @@ impl Config {
 pub fn fqdn<S: Display>(prompt: S) -> Result<String> {
     match cliclack::input(prompt)
         .validate(|input: &String| {
-            if input.is_empty() {
-                Err("Please enter a valid domain name".to_string())
-            } else if let Err(err) = addr::parse_domain_name(input) {
-                Err(err.to_string())
-            } else {
-                Ok(())
-            }
+            fqdn::parse(input)
+                .map(|_| ())
+                .map_err(|err| err.to_string())
         })
         .interact::<String>()
     {
@@ -141,9 +164,8 @@ the information using this form.
             return Err(Error::custom("Unable to detect public ip :("));
         }
     } else {
-        // TODO: validate space-separated fqdns
         let fqdns = fqdn("Enter fully qualified domain names (FQDN):")?;
-        ctx.config.fqdn = Some(fqdns.split_whitespace().map(String::from).collect());
+        ctx.config.fqdn = Some(fqdn::parse(&fqdns)?);
     }
     ctx.config.save()?;
     Ok(())
@@ -53,6 +53,10 @@ pub trait Action: Describe + Clone + Copy + Eq {
             let selection = selector.interact();
             INTERACTION.store(false, Ordering::Relaxed);
 
+            if let Ok(selection) = &selection {
+                history::set_action(selection.describe());
+            }
+
             match selection {
                 Ok(selection) => match selection.main(ctx) {
                     Ok(remain) => {
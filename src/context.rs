@@ -13,13 +13,13 @@ impl Context {
         let username = whoami::username();
         let system = Arc::new(System::default());
 
-        let config = match Config::load() {
-            Ok(config) => config,
-            Err(_) => {
-                let config = Config::try_new()?;
-                config.save()?;
-                config
-            }
+        // an unreadable config is reported rather than replaced with defaults
+        let config = if Config::exists() {
+            Config::load()?
+        } else {
+            let config = Config::try_new()?;
+            config.save()?;
+            config
         };
 
         Ok(Context {
@@ -38,6 +38,7 @@ impl Context {
             .map(|config| config.proxy_config(ctx))
             .collect::<Vec<_>>();
         services.push(self.config.resolver.proxy_config(ctx));
+        services.push(self.config.exporter.proxy_config(ctx));
         services.into_iter().flatten().flatten().collect::<Vec<_>>()
     }
 
@@ -49,6 +50,8 @@ impl Context {
             .map(|config| config.service_detail())
             .collect::<Vec<_>>();
         services.push(self.config.resolver.service_detail());
+        services.push(self.config.exporter.service_detail());
+        services.push(self.config.watchdog.service_detail());
         services.push(nginx::nginx_service_detail());
         services
     }
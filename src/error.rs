@@ -56,6 +56,12 @@ pub enum Error {
     #[error("Passwords do not match")]
     PasswordsDoNotMatch,
 
+    #[error("Invalid domain name `{0}`: {1}")]
+    Fqdn(String, String),
+
+    #[error("NGINX configuration test failed:\n{0}")]
+    NginxConfig(String),
+
     #[error("Resolver key mismatch: expected 0x{0:02x} got 0x{1:02x}")]
     ResolverKeyPrefix(u16, u16),
 }
@@ -0,0 +1,432 @@
+use crate::imports::*;
+use access::Access;
+use nginx::prelude::*;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::time::{Duration, Instant};
+
+pub const SERVICE_NAME: &str = "khost-exporter";
+pub const DEFAULT_PORT: u16 = 9410;
+/// NGINX location of the metrics endpoint
+pub const PROXY_PATH: &str = "/metrics";
+
+/// Addresses always allowed to reach a restricted metrics endpoint
+const LOOPBACK: &[&str] = &["127.0.0.1", "::1"];
+
+/// Interval between data folder size scans (`du` can be slow on large databases)
+const FOLDER_SIZE_INTERVAL: Duration = Duration::from_secs(300);
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Config {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: DEFAULT_PORT,
+        }
+    }
+}
+
+impl Service for Config {
+    fn service_title(&self) -> String {
+        "kHOST metrics exporter".to_string()
+    }
+
+    fn service_name(&self) -> String {
+        SERVICE_NAME.to_string()
+    }
+
+    fn kind(&self) -> ServiceKind {
+        ServiceKind::Exporter
+    }
+
+    fn origin(&self) -> Option<Origin> {
+        None
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn managed(&self) -> bool {
+        true
+    }
+
+    fn proxy_config(&self, ctx: &Context) -> Option<Vec<ProxyConfig>> {
+        let access = self.enabled.then(|| proxy_access(ctx)).flatten()?;
+        Some(vec![ProxyConfig::new(
+            format!("{} ({})", self.service_title(), self.service_name()),
+            PROXY_PATH,
+            ProxyKind::http(self.port),
+        )
+        .with_access(access)])
+    }
+}
+
+/// Access rules of the metrics endpoint, `None` while it is unrestricted
+/// (metrics are not proxied until an allow list or basic authentication is set)
+pub fn proxy_access(ctx: &Context) -> Option<Access> {
+    let mut access = ctx.config.nginx.access(PROXY_PATH);
+    if access.allow.is_empty() && access.auth.is_none() {
+        return None;
+    }
+    if !access.allow.is_empty() {
+        for address in LOOPBACK.iter().rev() {
+            if !access.allow.iter().any(|allow| allow == address) {
+                access.allow.insert(0, address.to_string());
+            }
+        }
+    }
+    Some(access)
+}
+
+/// Prometheus text exposition format (version 0.0.4)
+#[derive(Default)]
+struct Metrics {
+    text: String,
+}
+
+impl Metrics {
+    fn describe(&mut self, name: &str, kind: &str, help: &str) {
+        self.text.push_str(&format!("# HELP khost_{name} {help}\n"));
+        self.text.push_str(&format!("# TYPE khost_{name} {kind}\n"));
+    }
+
+    fn push<V: Display>(&mut self, name: &str, labels: &[(&str, &str)], value: V) {
+        let labels = labels
+            .iter()
+            .map(|(key, value)| {
+                let value = value
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('\n', "\\n");
+                format!("{key}=\"{value}\"")
+            })
+            .collect::<Vec<_>>();
+        if labels.is_empty() {
+            self.text.push_str(&format!("khost_{name} {value}\n"));
+        } else {
+            self.text
+                .push_str(&format!("khost_{name}{{{}}} {value}\n", labels.join(",")));
+        }
+    }
+}
+
+/// Unit properties readable without privileges
+struct UnitState {
+    active: bool,
+    enabled: bool,
+    restarts: u64,
+}
+
+fn unit_state(service_name: &str) -> UnitState {
+    let properties = systemd::show(service_name, &["ActiveState", "UnitFileState", "NRestarts"]);
+    let property = |name: &str| properties.get(name).map(String::as_str);
+    UnitState {
+        active: property("ActiveState") == Some("active"),
+        enabled: property("UnitFileState") == Some("enabled"),
+        restarts: property("NRestarts")
+            .and_then(|value| value.parse().ok())
+            .unwrap_or_default(),
+    }
+}
+
+struct Exporter<'a> {
+    ctx: &'a Context,
+    folder_sizes: Vec<(String, u64)>,
+    folder_sizes_updated: Option<Instant>,
+}
+
+impl<'a> Exporter<'a> {
+    fn new(ctx: &'a Context) -> Self {
+        Self {
+            ctx,
+            folder_sizes: Vec::new(),
+            folder_sizes_updated: None,
+        }
+    }
+
+    fn folder_sizes(&mut self) -> &[(String, u64)] {
+        if self
+            .folder_sizes_updated
+            .is_none_or(|updated| updated.elapsed() > FOLDER_SIZE_INTERVAL)
+        {
+            self.folder_sizes = storage::data_folders(self.ctx)
+                .into_iter()
+                .filter_map(|(service, path)| {
+                    storage::folder_size(&path).map(|size| (service, size))
+                })
+                .collect();
+            self.folder_sizes_updated = Some(Instant::now());
+        }
+        &self.folder_sizes
+    }
+
+    fn render(&mut self) -> String {
+        let ctx = self.ctx;
+        let mut metrics = Metrics::default();
+
+        metrics.describe("info", "gauge", "kHOST version");
+        metrics.push("info", &[("version", khost::VERSION)], 1);
+
+        let services = ctx.active_services();
+        let states = services
+            .iter()
+            .map(|service| (service, unit_state(&service.name)))
+            .collect::<Vec<_>>();
+
+        metrics.describe("service_active", "gauge", "Service unit is active");
+        for (service, state) in states.iter() {
+            metrics.push(
+                "service_active",
+                &[("service", &service.name)],
+                state.active as u8,
+            );
+        }
+        metrics.describe("service_enabled", "gauge", "Service unit is enabled");
+        for (service, state) in states.iter() {
+            metrics.push(
+                "service_enabled",
+                &[("service", &service.name)],
+                state.enabled as u8,
+            );
+        }
+        metrics.describe(
+            "service_restarts_total",
+            "counter",
+            "Service restarts performed by systemd",
+        );
+        for (service, state) in states.iter() {
+            metrics.push(
+                "service_restarts_total",
+                &[("service", &service.name)],
+                state.restarts,
+            );
+        }
+
+        metrics.describe("service_build_info", "gauge", "Service version and commit");
+        for service in services.iter() {
+            let Some(origin) = service.origin.as_ref() else {
+                continue;
+            };
+            let (folder, version) = match service.kind {
+                ServiceKind::Kaspad(_) => (kaspad::folder(origin), kaspad::version(origin)),
+                ServiceKind::Resolver => (resolver::folder(origin), resolver::version(origin)),
+                _ => continue,
+            };
+            let commit = git::hash(folder, true).unwrap_or_default();
+            // versions are reported as `<version>-<commit>`
+            let version = version.unwrap_or_default();
+            let version = version
+                .strip_suffix(&format!("-{commit}"))
+                .unwrap_or(&version);
+            metrics.push(
+                "service_build_info",
+                &[
+                    ("service", &service.name),
+                    ("origin", &origin.to_string()),
+                    ("version", version),
+                    ("commit", &commit),
+                ],
+                1,
+            );
+        }
+
+        metrics.describe("data_folder_bytes", "gauge", "Size of service data folders");
+        for (service, size) in self.folder_sizes() {
+            metrics.push("data_folder_bytes", &[("service", service)], size);
+        }
+
+        if let Some(disk_usage) = system::disk_usage() {
+            metrics.describe(
+                "disk_total_bytes",
+                "gauge",
+                "Total size of the home filesystem",
+            );
+            metrics.push("disk_total_bytes", &[], disk_usage.total);
+            metrics.describe(
+                "disk_used_bytes",
+                "gauge",
+                "Used space on the home filesystem",
+            );
+            metrics.push("disk_used_bytes", &[], disk_usage.used);
+            metrics.describe(
+                "disk_available_bytes",
+                "gauge",
+                "Available space on the home filesystem",
+            );
+            metrics.push("disk_available_bytes", &[], disk_usage.available);
+        }
+
+        let health = health::detect(ctx);
+        let mut health = health
+            .iter()
+            .filter_map(|(service, health)| health.as_ref().ok().map(|health| (service, health)))
+            .collect::<Vec<_>>();
+        health.sort_by_key(|(service, _)| *service);
+
+        metrics.describe("node_up", "gauge", "Node wRPC interface is responding");
+        for config in kaspad::active_configs(ctx) {
+            let service = config.service_name();
+            let up = health.iter().any(|(name, _)| **name == service);
+            metrics.push("node_up", &[("service", &service)], up as u8);
+        }
+        metrics.describe("node_synced", "gauge", "Node is synced");
+        for (service, health) in health.iter() {
+            metrics.push(
+                "node_synced",
+                &[("service", service)],
+                health.is_synced as u8,
+            );
+        }
+        metrics.describe("node_virtual_daa_score", "gauge", "Virtual DAA score");
+        for (service, health) in health.iter() {
+            metrics.push(
+                "node_virtual_daa_score",
+                &[("service", service)],
+                health.virtual_daa_score,
+            );
+        }
+        metrics.describe("node_headers", "gauge", "Header count");
+        for (service, health) in health.iter() {
+            metrics.push("node_headers", &[("service", service)], health.header_count);
+        }
+        metrics.describe("node_blocks", "gauge", "Block count");
+        for (service, health) in health.iter() {
+            metrics.push("node_blocks", &[("service", service)], health.block_count);
+        }
+        metrics.describe("node_peers", "gauge", "Connected p2p peers");
+        for (service, health) in health.iter() {
+            metrics.push(
+                "node_peers",
+                &[("service", service), ("direction", "inbound")],
+                health.inbound_peers,
+            );
+            metrics.push(
+                "node_peers",
+                &[("service", service), ("direction", "outbound")],
+                health.outbound_peers,
+            );
+        }
+        metrics.describe("node_mempool_size", "gauge", "Mempool transaction count");
+        for (service, health) in health.iter() {
+            metrics.push(
+                "node_mempool_size",
+                &[("service", service)],
+                health.mempool_size,
+            );
+        }
+
+        metrics.text
+    }
+
+    fn handle(&mut self, stream: TcpStream) -> Result<()> {
+        stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let mut request = String::new();
+        reader.read_line(&mut request)?;
+        // drain request headers
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                break;
+            }
+        }
+
+        let mut parts = request.split_whitespace();
+        let method = parts.next().unwrap_or_default();
+        let path = parts.next().unwrap_or_default();
+
+        // NGINX strips the `/metrics` location prefix
+        let (status, body) = match (method, path) {
+            ("GET", "/" | "/metrics") => ("200 OK", self.render()),
+            _ => ("404 Not Found", "Not found\n".to_string()),
+        };
+
+        let mut stream = stream;
+        write!(
+            stream,
+            "HTTP/1.1 {status}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )?;
+        Ok(())
+    }
+}
+
+/// Serve `/metrics` until the process is terminated (`khost exporter`)
+pub fn run(ctx: &Context, listen: SocketAddr) -> Result<()> {
+    let listener = TcpListener::bind(listen)?;
+    println!("kHOST exporter listening on http://{listen}/metrics");
+
+    let mut exporter = Exporter::new(ctx);
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(err) = exporter.handle(stream) {
+                    println!("exporter: {err}");
+                }
+            }
+            Err(err) => println!("exporter: {err}"),
+        }
+    }
+
+    Ok(())
+}
+
+pub fn listen_address(config: &Config) -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], config.port))
+}
+
+pub fn create_systemd_unit(ctx: &Context, config: &Config) -> Result<()> {
+    let exec_start = vec![
+        khost::binary()?.display().to_string(),
+        "exporter".to_string(),
+        format!("--listen={}", listen_address(config)),
+    ];
+
+    let unit_config = systemd::Config::new(
+        config,
+        "kHOST metrics exporter",
+        &ctx.username,
+        exec_start,
+        5,
+    );
+
+    systemd::create(unit_config)?;
+
+    Ok(())
+}
+
+pub fn reconfigure(ctx: &Context) -> Result<()> {
+    let config = &ctx.config.exporter;
+
+    if config.enabled() {
+        step("Configuring 'khost-exporter'", || {
+            create_systemd_unit(ctx, config)?;
+            systemd::daemon_reload()?;
+            systemd::enable(config)?;
+            systemd::restart(config)?;
+            Ok(())
+        })?;
+        if proxy_access(ctx).is_none() {
+            log::info(format!(
+                "Metrics are served on 127.0.0.1:{} only; restrict `{PROXY_PATH}` via 'Configure endpoint access' to proxy them",
+                config.port
+            ))?;
+        }
+    } else if systemd::exists(config) {
+        step("Removing 'khost-exporter'", || {
+            systemd::stop(config)?;
+            systemd::disable(config)?;
+            systemd::remove(config)?;
+            systemd::daemon_reload()?;
+            Ok(())
+        })?;
+    }
+
+    Ok(())
+}
@@ -1,3 +1,6 @@
+use crate::imports::*;
+
+/// Domains served by the Kaspa public node network
 const DOMAINS: &[&str] = &[
     "kaspa.stream",
     "kaspa.red",
@@ -10,10 +13,34 @@ const DOMAINS: &[&str] = &[
     "kaspacalc.net",
 ];
 
-pub fn get() -> Vec<String> {
+/// NGINX catch-all server name
+const CATCH_ALL: &str = "_";
+
+pub fn public() -> Vec<String> {
     DOMAINS.iter().map(|fqdn| format!("*.{fqdn}")).collect()
 }
 
+/// FQDNs configured by the user
+pub fn configured(ctx: &Context) -> Vec<String> {
+    ctx.config.fqdn.clone().unwrap_or_default()
+}
+
+/// FQDNs served by NGINX: the public domain list when participating in
+/// the public node network, otherwise the user-configured list or a
+/// catch-all server name if none are configured.
+pub fn get(ctx: &Context) -> Vec<String> {
+    if ctx.config.public {
+        public()
+    } else {
+        let fqdns = configured(ctx);
+        if fqdns.is_empty() {
+            vec![CATCH_ALL.to_string()]
+        } else {
+            fqdns
+        }
+    }
+}
+
 pub fn flatten(fqdns: &[String]) -> String {
     fqdns
         .iter()
@@ -21,3 +48,75 @@ pub fn flatten(fqdns: &[String]) -> String {
         .collect::<Vec<_>>()
         .join(" ")
 }
+
+/// Validate domain name syntax; a leading `*.` wildcard label is accepted.
+/// Private names (e.g. `node.lan`) are valid for self-signed and PKI certificates.
+pub fn validate(fqdn: &str) -> Result<()> {
+    let domain = fqdn.strip_prefix("*.").unwrap_or(fqdn);
+    if domain.is_empty() {
+        return Err(Error::Fqdn(
+            fqdn.to_string(),
+            "empty domain name".to_string(),
+        ));
+    }
+    addr::parse_domain_name(domain)
+        .map(|_| ())
+        .map_err(|err| Error::Fqdn(fqdn.to_string(), err.to_string()))
+}
+
+/// Domain names under a public suffix (required for ACME validation)
+pub fn has_public_suffix(fqdn: &str) -> bool {
+    let domain = fqdn.strip_prefix("*.").unwrap_or(fqdn);
+    addr::parse_domain_name(domain).is_ok_and(|name| name.has_known_suffix())
+}
+
+/// Parse and validate a whitespace-separated list of domain names
+pub fn parse(input: &str) -> Result<Vec<String>> {
+    let fqdns = input
+        .split_whitespace()
+        .map(|fqdn| fqdn.to_lowercase())
+        .collect::<Vec<_>>();
+
+    if fqdns.is_empty() {
+        return Err(Error::custom("Please enter a valid domain name"));
+    }
+
+    for fqdn in fqdns.iter() {
+        validate(fqdn)?;
+    }
+
+    Ok(fqdns)
+}
+
+/// Add domain names to the configured list, returns `true` if the list changed
+pub fn add(ctx: &mut Context, fqdns: Vec<String>) -> Result<bool> {
+    let mut list = configured(ctx);
+    let len = list.len();
+    for fqdn in fqdns {
+        validate(&fqdn)?;
+        if !list.contains(&fqdn) {
+            list.push(fqdn);
+        }
+    }
+
+    let changed = list.len() != len;
+    if changed {
+        ctx.config.fqdn = Some(list);
+        ctx.config.save()?;
+    }
+    Ok(changed)
+}
+
+/// Remove domain names from the configured list, returns `true` if the list changed
+pub fn remove(ctx: &mut Context, fqdns: &[String]) -> Result<bool> {
+    let mut list = configured(ctx);
+    let len = list.len();
+    list.retain(|fqdn| !fqdns.contains(fqdn));
+
+    let changed = list.len() != len;
+    if changed {
+        ctx.config.fqdn = (!list.is_empty()).then_some(list);
+        ctx.config.save()?;
+    }
+    Ok(changed)
+}
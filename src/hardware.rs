@@ -0,0 +1,425 @@
+use crate::imports::*;
+use status::Conflict;
+use std::io::{Seek, SeekFrom, Write};
+use std::time::{Duration, Instant};
+
+/// RAM reserved for the operating system, NGINX and kHOST services
+const SYSTEM_RAM_GB: u64 = 2;
+/// Reported memory excludes kernel reservations; accept this shortfall
+const RAM_TOLERANCE_GB: f64 = 1.0;
+
+/// Sequential probe size
+const PROBE_SIZE: usize = 64 * 1024 * 1024;
+const PROBE_BLOCK: usize = 1024 * 1024;
+/// Random probe write size (synced individually)
+const PROBE_PAGE: usize = 4096;
+const PROBE_RANDOM_WRITES: u64 = 256;
+const PROBE_RANDOM_LIMIT: Duration = Duration::from_secs(3);
+
+/// Filesystems unsuitable for node databases
+const NETWORK_FILESYSTEMS: &[&str] = &["nfs", "nfs4", "cifs", "smb3", "fuse.sshfs", "9p"];
+const VOLATILE_FILESYSTEMS: &[&str] = &["tmpfs", "ramfs"];
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Role {
+    Node(Network),
+    /// Mainnet node retaining full history
+    Archival,
+    Resolver,
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::Node(network) => write!(f, "{network} node"),
+            Role::Archival => write!(f, "mainnet archival node"),
+            Role::Resolver => write!(f, "resolver"),
+        }
+    }
+}
+
+/// Recommended resources for a role
+#[derive(Debug, Clone, Copy)]
+pub struct Requirements {
+    pub ram_gb: u64,
+    pub cores: usize,
+    pub disk_gb: u64,
+    /// Synced random 4K writes per second
+    pub iops: u64,
+}
+
+impl Role {
+    pub fn iter() -> impl Iterator<Item = Role> {
+        Network::iter()
+            .copied()
+            .map(Role::Node)
+            .chain([Role::Archival, Role::Resolver])
+    }
+
+    pub fn requirements(&self) -> Requirements {
+        let (ram_gb, cores, disk_gb, iops) = match self {
+            Role::Node(Network::Mainnet) => (16, 4, 200, 1000),
+            Role::Node(Network::Testnet10) => (14, 4, 150, 1000),
+            Role::Node(Network::Testnet11) => (12, 4, 200, 1000),
+            Role::Archival => (32, 8, 2000, 3000),
+            Role::Resolver => (1, 1, 1, 0),
+        };
+        Requirements {
+            ram_gb,
+            cores,
+            disk_gb,
+            iops,
+        }
+    }
+}
+
+/// Storage throughput measured in the data folder
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IoProbe {
+    pub time: chrono::DateTime<chrono::Local>,
+    pub folder: PathBuf,
+    /// Sequential write bytes per second
+    pub sequential: f64,
+    /// Synced random 4K writes per second
+    pub random_iops: f64,
+}
+
+impl IoProbe {
+    fn filename() -> PathBuf {
+        data_folder().join("io-probe.json")
+    }
+
+    /// Last probe result for the folder
+    pub fn cached(folder: &Path) -> Option<Self> {
+        let probe: Self = serde_json::from_str(&fs::read_to_string(Self::filename()).ok()?).ok()?;
+        (probe.folder == folder).then_some(probe)
+    }
+
+    pub fn run(folder: &Path) -> Result<Self> {
+        let path = folder.join(".khost-io-probe");
+        let result = Self::measure(folder, &path);
+        fs::remove_file(&path).ok();
+        let probe = result?;
+        fs::write(Self::filename(), serde_json::to_string(&probe)?)?;
+        Ok(probe)
+    }
+
+    fn measure(folder: &Path, path: &Path) -> Result<Self> {
+        let block = vec![0x5a; PROBE_BLOCK];
+
+        let start = Instant::now();
+        let mut file = fs::File::create(path)?;
+        for _ in 0..PROBE_SIZE / PROBE_BLOCK {
+            file.write_all(&block)?;
+        }
+        file.sync_all()?;
+        let sequential = PROBE_SIZE as f64 / start.elapsed().as_secs_f64();
+
+        let pages = (PROBE_SIZE / PROBE_PAGE) as u64;
+        let start = Instant::now();
+        let mut writes = 0;
+        while writes < PROBE_RANDOM_WRITES && start.elapsed() < PROBE_RANDOM_LIMIT {
+            let page = xxh3_64(&writes.to_le_bytes()) % pages;
+            file.seek(SeekFrom::Start(page * PROBE_PAGE as u64))?;
+            file.write_all(&block[..PROBE_PAGE])?;
+            file.sync_data()?;
+            writes += 1;
+        }
+        let random_iops = writes as f64 / start.elapsed().as_secs_f64();
+
+        Ok(Self {
+            time: chrono::Local::now(),
+            folder: folder.to_path_buf(),
+            sequential,
+            random_iops,
+        })
+    }
+}
+
+/// Host resources relevant to node operation
+#[derive(Debug, Clone)]
+pub struct Assessment {
+    pub total_memory: u64,
+    pub cores: Option<usize>,
+    /// Nearest existing folder on the path to the node data folders
+    pub folder: PathBuf,
+    pub available: Option<u64>,
+    pub filesystem: Option<String>,
+    pub io: Option<IoProbe>,
+}
+
+/// Nearest existing ancestor of the node data folders
+fn target_folder(ctx: &Context) -> PathBuf {
+    let data_folder = kaspad::active_configs(ctx)
+        .next()
+        .or_else(|| ctx.config.kaspad.first())
+        .map(|config| config.data_folder())
+        .unwrap_or_else(home_folder);
+    data_folder
+        .ancestors()
+        .find(|path| path.is_dir())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(home_folder)
+}
+
+impl Assessment {
+    /// Detect resources; `probe` runs a fresh I/O probe, otherwise the last result is used
+    pub fn detect(ctx: &Context, probe: bool) -> Self {
+        let folder = target_folder(ctx);
+        let (available, filesystem) = system::filesystem(&folder)
+            .map(|filesystem| (filesystem.available, filesystem.fstype))
+            .unzip();
+        let io = if probe {
+            match IoProbe::run(&folder) {
+                Ok(io) => Some(io),
+                Err(err) => {
+                    log::warning(format!("Unable to probe storage performance: {err}")).ok();
+                    None
+                }
+            }
+        } else {
+            IoProbe::cached(&folder)
+        };
+
+        Self {
+            total_memory: ctx.system.total_memory,
+            cores: ctx.system.cpu_physical_cores,
+            folder,
+            available,
+            filesystem,
+            io,
+        }
+    }
+
+    fn ram_gb(&self) -> f64 {
+        self.total_memory as f64 / 1024.0 / 1024.0 / 1024.0
+    }
+
+    fn has_ram(&self, required_gb: u64) -> bool {
+        self.ram_gb() + RAM_TOLERANCE_GB >= required_gb as f64
+    }
+
+    fn available_gb(&self) -> Option<u64> {
+        self.available.map(|bytes| bytes / 1_000_000_000)
+    }
+
+    /// Storage issues that apply to every role
+    fn storage_issues(&self, iops: u64) -> Vec<Conflict> {
+        let mut issues = Vec::new();
+        if let Some(filesystem) = &self.filesystem {
+            if NETWORK_FILESYSTEMS.contains(&filesystem.as_str()) {
+                issues.push(Conflict::warning(format!(
+                    "Data folder `{}` is on a network filesystem ({filesystem}); use local ext4 or xfs storage",
+                    self.folder.display()
+                )));
+            } else if VOLATILE_FILESYSTEMS.contains(&filesystem.as_str()) {
+                issues.push(Conflict::error(format!(
+                    "Data folder `{}` is on a volatile filesystem ({filesystem}); node data will be lost on restart",
+                    self.folder.display()
+                )));
+            }
+        }
+        if let Some(io) = &self.io {
+            if (io.random_iops as u64) < iops {
+                issues.push(Conflict::warning(format!(
+                    "Storage sustains {:1.0} synced random writes/s (recommended {iops}+); use SSD or NVMe storage",
+                    io.random_iops
+                )));
+            }
+        }
+        issues
+    }
+
+    /// Issues preventing (errors) or degrading (warnings) a single role
+    pub fn assess(&self, role: Role) -> Vec<Conflict> {
+        let required = role.requirements();
+        let mut issues = Vec::new();
+        // a single role below the recommendation still runs (slower); only warn
+        let ram_gb = required.ram_gb + SYSTEM_RAM_GB;
+        if !self.has_ram(ram_gb) {
+            issues.push(Conflict::warning(format!(
+                "{role} recommends {ram_gb} GB RAM, {:1.0} GB detected",
+                self.ram_gb()
+            )));
+        }
+        if let Some(cores) = self.cores.filter(|cores| *cores < required.cores) {
+            issues.push(Conflict::warning(format!(
+                "{role} recommends {} physical cores, {cores} detected",
+                required.cores
+            )));
+        }
+        if let Some(available) = self
+            .available_gb()
+            .filter(|available| *available < required.disk_gb)
+        {
+            issues.push(Conflict::warning(format!(
+                "{role} requires {} GB free disk space, {available} GB available at `{}`",
+                required.disk_gb,
+                self.folder.display()
+            )));
+        }
+        if role != Role::Resolver {
+            issues.extend(self.storage_issues(required.iops));
+        }
+        issues
+    }
+
+    /// Issues running the given networks side by side
+    pub fn check_networks(&self, ctx: &Context, networks: &[Network]) -> Vec<Conflict> {
+        if networks.is_empty() {
+            return vec![];
+        }
+        let roles = networks
+            .iter()
+            .map(|network| Role::Node(*network))
+            .collect::<Vec<_>>();
+        let mut issues = Vec::new();
+
+        let ram_gb = roles
+            .iter()
+            .map(|role| role.requirements().ram_gb)
+            .sum::<u64>()
+            + SYSTEM_RAM_GB;
+        if !self.has_ram(ram_gb) {
+            let names = networks
+                .iter()
+                .map(|network| network.to_string())
+                .collect::<Vec<_>>()
+                .join(" + ");
+            let message = format!(
+                "Detected RAM of {:1.0} GB is insufficient for {names}; at least {ram_gb} GB is recommended",
+                self.ram_gb()
+            );
+            // only nodes running side by side are blocked
+            if roles.len() > 1 {
+                issues.push(Conflict::error(message));
+            } else {
+                issues.push(Conflict::warning(message));
+            }
+        }
+
+        let cores = roles
+            .iter()
+            .map(|role| role.requirements().cores)
+            .max()
+            .unwrap_or_default()
+            + 2 * (roles.len() - 1);
+        if let Some(detected) = self.cores.filter(|detected| *detected < cores) {
+            issues.push(Conflict::warning(format!(
+                "Selected networks recommend {cores} physical cores, {detected} detected"
+            )));
+        }
+
+        // only networks without existing data need additional space
+        let disk_gb = ctx
+            .config
+            .kaspad
+            .iter()
+            .filter(|config| networks.contains(&config.network()) && !config.data_folder().exists())
+            .map(|config| Role::Node(config.network()).requirements().disk_gb)
+            .sum::<u64>();
+        if let Some(available) = self.available_gb().filter(|available| *available < disk_gb) {
+            issues.push(Conflict::warning(format!(
+                "Selected networks require {disk_gb} GB free disk space, {available} GB available at `{}`",
+                self.folder.display()
+            )));
+        }
+
+        let iops = roles
+            .iter()
+            .map(|role| role.requirements().iops)
+            .max()
+            .unwrap_or_default();
+        issues.extend(self.storage_issues(iops));
+        issues
+    }
+
+    /// Whether RAM allows at least the two smallest networks side by side
+    pub fn supports_multiple_networks(&self) -> bool {
+        let mut ram = Network::iter()
+            .copied()
+            .map(|network| Role::Node(network).requirements().ram_gb)
+            .collect::<Vec<_>>();
+        ram.sort();
+        self.has_ram(ram.iter().take(2).sum::<u64>() + SYSTEM_RAM_GB)
+    }
+}
+
+impl Display for Assessment {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut rows = vec![
+            Content::field("RAM:", format!("{:1.0} GB", self.ram_gb())),
+            Content::field(
+                "Physical cores:",
+                self.cores
+                    .map(|cores| cores.to_string())
+                    .unwrap_or_else(|| "N/A".to_string()),
+            ),
+            Content::field("Data folder:", self.folder.display()),
+            Content::field("Filesystem:", self.filesystem.as_deref().unwrap_or("N/A")),
+            Content::field(
+                "Free disk:",
+                self.available
+                    .map(|bytes| as_data_size(bytes as f64, true))
+                    .unwrap_or_else(|| "N/A".to_string()),
+            ),
+        ];
+        if let Some(io) = &self.io {
+            rows.push(Content::field(
+                "Storage:",
+                format!(
+                    "{}/s sequential, {:1.0} synced random writes/s ({})",
+                    as_data_size(io.sequential, true),
+                    io.random_iops,
+                    io.time.format("%Y-%m-%d")
+                ),
+            ));
+        }
+        rows.push(Content::separator());
+
+        for role in Role::iter() {
+            let issues = self.assess(role);
+            let verdict = if issues
+                .iter()
+                .any(|issue| matches!(issue, Conflict::Error(_)))
+            {
+                style("insufficient").red().bright()
+            } else if issues.is_empty() {
+                style("supported").green().bright()
+            } else {
+                style("supported with warnings").yellow().bright()
+            };
+            rows.push(Content::field(format!("{role}:"), verdict));
+            for issue in issues {
+                let text = match issue {
+                    Conflict::Error(text) => style(text).red(),
+                    Conflict::Warning(text) => style(text).yellow(),
+                };
+                rows.push(Content::field("", text));
+            }
+        }
+
+        write!(f, "{}", content(rows))
+    }
+}
+
+/// Full assessment with a fresh storage probe
+pub fn report(ctx: &Context) -> Result<()> {
+    let mut assessment = None;
+    step("Probing storage performance...", || {
+        assessment = Some(Assessment::detect(ctx, true));
+        Ok(())
+    })?;
+    if let Some(assessment) = assessment {
+        log::info(assessment)?;
+    }
+    Ok(())
+}
+
+/// Startup check of the enabled networks (uses the last storage probe)
+pub fn conflicts(ctx: &Context) -> Vec<Conflict> {
+    let networks = kaspad::active_configs(ctx)
+        .map(|config| config.network())
+        .collect::<Vec<_>>();
+    Assessment::detect(ctx, false).check_networks(ctx, &networks)
+}
@@ -0,0 +1,165 @@
+use crate::imports::*;
+use serde_json::{json, Value};
+use std::net::SocketAddr;
+use std::time::Duration;
+use wrpc::Client;
+
+/// Connection and per-call timeout for local node queries
+pub const TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Live node state reported by the local wRPC interface
+#[derive(Debug, Clone, Default)]
+pub struct Health {
+    pub server_version: String,
+    pub network_id: String,
+    pub is_synced: bool,
+    pub virtual_daa_score: u64,
+    pub header_count: u64,
+    pub block_count: u64,
+    pub inbound_peers: usize,
+    pub outbound_peers: usize,
+    pub mempool_size: u64,
+}
+
+impl Display for Health {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "v{} {} · DAA {} · headers {} · blocks {} · peers {} in / {} out · mempool {}",
+            self.server_version,
+            if self.is_synced { "synced" } else { "syncing" },
+            self.virtual_daa_score,
+            self.header_count,
+            self.block_count,
+            self.inbound_peers,
+            self.outbound_peers,
+            self.mempool_size
+        )
+    }
+}
+
+/// Numeric field that may be serialized as a number or a string
+fn u64_field(value: &Value, name: &str) -> u64 {
+    match value.get(name) {
+        Some(Value::Number(n)) => n.as_u64().unwrap_or_default(),
+        Some(Value::String(s)) => s.parse().unwrap_or_default(),
+        _ => 0,
+    }
+}
+
+fn str_field(value: &Value, name: &str) -> String {
+    value
+        .get(name)
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Query a node wRPC (json) interface
+pub fn fetch(addr: SocketAddr, timeout: Duration) -> Result<Health> {
+    let mut client = Client::connect(addr, timeout)?;
+
+    let server_info = client.call("getServerInfo", json!({}))?;
+    let dag_info = client.call("getBlockDagInfo", json!({}))?;
+    let peer_info = client.call("getConnectedPeerInfo", json!({}))?;
+    let info = client.call("getInfo", json!({}))?;
+    client.close();
+
+    Ok(parse(&server_info, &dag_info, &peer_info, &info))
+}
+
+/// Build node state from `getServerInfo`, `getBlockDagInfo`, `getConnectedPeerInfo` and `getInfo` responses
+fn parse(server_info: &Value, dag_info: &Value, peer_info: &Value, info: &Value) -> Health {
+    let peers = peer_info
+        .get("peerInfo")
+        .and_then(Value::as_array)
+        .cloned()
+        .unwrap_or_default();
+    let outbound_peers = peers
+        .iter()
+        .filter(|peer| peer.get("isOutbound").and_then(Value::as_bool) == Some(true))
+        .count();
+
+    Health {
+        server_version: str_field(server_info, "serverVersion"),
+        network_id: str_field(server_info, "networkId"),
+        is_synced: server_info
+            .get("isSynced")
+            .and_then(Value::as_bool)
+            .unwrap_or_default(),
+        virtual_daa_score: u64_field(server_info, "virtualDaaScore"),
+        header_count: u64_field(dag_info, "headerCount"),
+        block_count: u64_field(dag_info, "blockCount"),
+        inbound_peers: peers.len() - outbound_peers,
+        outbound_peers,
+        mempool_size: u64_field(info, "mempoolSize"),
+    }
+}
+
+/// Query all active node instances concurrently, keyed by service name
+pub fn detect(ctx: &Context) -> HashMap<String, std::result::Result<Health, String>> {
+    let targets = kaspad::active_configs(ctx)
+        .filter_map(|config| {
+            config
+                .wrpc_json_address()
+                .map(|addr| (config.service_name(), addr))
+        })
+        .collect::<Vec<_>>();
+
+    std::thread::scope(|scope| {
+        targets
+            .into_iter()
+            .map(|(service, addr)| {
+                scope.spawn(move || (service, fetch(addr, TIMEOUT).map_err(|e| e.to_string())))
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .filter_map(|handle| handle.join().ok())
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_node_responses() {
+        let server_info = json!({
+            "serverVersion": "0.16.1",
+            "networkId": "mainnet",
+            "isSynced": true,
+            "virtualDaaScore": "92814551",
+        });
+        let dag_info = json!({ "headerCount": 1234, "blockCount": "1200" });
+        let peer_info = json!({
+            "peerInfo": [
+                { "isOutbound": true },
+                { "isOutbound": false },
+                { "isOutbound": true },
+                {},
+            ]
+        });
+        let info = json!({ "mempoolSize": 17 });
+
+        let health = parse(&server_info, &dag_info, &peer_info, &info);
+        assert_eq!(health.server_version, "0.16.1");
+        assert_eq!(health.network_id, "mainnet");
+        assert!(health.is_synced);
+        assert_eq!(health.virtual_daa_score, 92814551);
+        assert_eq!(health.header_count, 1234);
+        assert_eq!(health.block_count, 1200);
+        assert_eq!(health.outbound_peers, 2);
+        assert_eq!(health.inbound_peers, 2);
+        assert_eq!(health.mempool_size, 17);
+    }
+
+    #[test]
+    fn tolerates_missing_fields() {
+        let empty = json!({});
+        let health = parse(&empty, &empty, &empty, &empty);
+        assert!(!health.is_synced);
+        assert_eq!(health.virtual_daa_score, 0);
+        assert_eq!(health.inbound_peers + health.outbound_peers, 0);
+    }
+}
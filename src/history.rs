@@ -0,0 +1,273 @@
+use crate::imports::*;
+use chrono::{DateTime, Local};
+use serde_json::Value;
+use std::sync::Mutex;
+
+/// Snapshots kept in the history folder (oldest are removed first)
+const MAX_SNAPSHOTS: usize = 50;
+
+/// Attribution of saves made outside of menu actions
+const DEFAULT_ACTION: &str = "kHOST";
+
+/// Menu action responsible for config changes
+static ACTION: Mutex<&str> = Mutex::new(DEFAULT_ACTION);
+
+/// Attribute subsequent config saves to `action`
+pub fn set_action(action: &'static str) {
+    if let Ok(mut current) = ACTION.lock() {
+        *current = action;
+    }
+}
+
+fn action() -> &'static str {
+    ACTION
+        .lock()
+        .map(|action| *action)
+        .unwrap_or(DEFAULT_ACTION)
+}
+
+pub fn folder() -> PathBuf {
+    data_folder().join("config-history")
+}
+
+/// Saved config with the action that caused the save
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub time: DateTime<Local>,
+    pub action: String,
+    /// Raw config (may predate the current config version)
+    pub config: Value,
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+impl Display for Snapshot {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} - {}",
+            self.time.format("%Y-%m-%d %H:%M:%S"),
+            self.action
+        )
+    }
+}
+
+impl PartialEq for Snapshot {
+    fn eq(&self, other: &Self) -> bool {
+        self.path == other.path
+    }
+}
+
+impl Eq for Snapshot {}
+
+/// Snapshots, most recent first
+pub fn snapshots() -> Vec<Snapshot> {
+    let mut snapshots = fs::read_dir(folder())
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| {
+                    let path = entry.path();
+                    let mut snapshot: Snapshot =
+                        serde_json::from_str(&fs::read_to_string(&path).ok()?).ok()?;
+                    snapshot.path = path;
+                    Some(snapshot)
+                })
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+    snapshots.sort_by_key(|snapshot| std::cmp::Reverse(snapshot.time));
+    snapshots
+}
+
+/// Add a snapshot unless the config matches the most recent one
+pub fn record(config: Value, action: &str) -> Result<()> {
+    let snapshots = snapshots();
+    if snapshots
+        .first()
+        .is_some_and(|latest| latest.config == config)
+    {
+        return Ok(());
+    }
+
+    // keep snapshots (and their file names) strictly ordered when recorded back to back
+    let time = snapshots
+        .first()
+        .map(|latest| latest.time + chrono::Duration::milliseconds(1))
+        .map_or_else(Local::now, |next| next.max(Local::now()));
+    let snapshot = Snapshot {
+        time,
+        action: action.to_string(),
+        config,
+        path: PathBuf::new(),
+    };
+    fs::create_dir_all(folder())?;
+    fs::write(
+        folder().join(format!("{}.json", time.format("%Y%m%d-%H%M%S-%3f"))),
+        serde_json::to_string_pretty(&snapshot)?,
+    )?;
+
+    for snapshot in snapshots.iter().skip(MAX_SNAPSHOTS - 1) {
+        fs::remove_file(&snapshot.path).ok();
+    }
+    Ok(())
+}
+
+/// Snapshot a config being saved by the current action
+pub fn record_save(config: &Config) -> Result<()> {
+    record(serde_json::to_value(config)?, action())
+}
+
+/// Snapshot the config file before it is deleted by `--reset`
+pub fn record_reset() -> Result<()> {
+    let config = serde_json::from_str(&fs::read_to_string(Config::filename())?)?;
+    record(config, "Reset configuration")
+}
+
+fn unified_diff(current: &Value, snapshot: &Value) -> Result<String> {
+    let current_path = temp_folder().join("config-current.json");
+    let snapshot_path = temp_folder().join("config-snapshot.json");
+    fs::write(&current_path, serde_json::to_string_pretty(current)? + "\n")?;
+    fs::write(
+        &snapshot_path,
+        serde_json::to_string_pretty(snapshot)? + "\n",
+    )?;
+    let diff = cmd!(
+        "diff",
+        "-u",
+        "--label",
+        "current",
+        "--label",
+        "snapshot",
+        &current_path,
+        &snapshot_path
+    )
+    .unchecked()
+    .read();
+    fs::remove_file(current_path).ok();
+    fs::remove_file(snapshot_path).ok();
+    diff
+}
+
+/// Stop and remove node units whose configs are absent from the restored config
+/// (e.g. instances added after the snapshot); data folders are kept
+fn remove_orphans(ctx: &Context, previous: &Config) -> Result<()> {
+    let orphans = previous
+        .kaspad
+        .iter()
+        .filter(|config| {
+            !ctx.config
+                .kaspad
+                .iter()
+                .any(|restored| restored.service_name() == config.service_name())
+        })
+        .filter(|config| systemd::exists(*config))
+        .collect::<Vec<_>>();
+    for config in orphans.iter() {
+        step(
+            format!("Removing service '{}'", config.service_name()),
+            || {
+                systemd::stop(*config)?;
+                systemd::disable(*config)?;
+                systemd::remove(*config)
+            },
+        )?;
+    }
+    if !orphans.is_empty() {
+        systemd::daemon_reload()?;
+    }
+    Ok(())
+}
+
+/// Apply the services affected by differences between two configs
+fn reconfigure(ctx: &mut Context, previous: &Config) -> Result<()> {
+    let previous_value = serde_json::to_value(previous)?;
+    let current_value = serde_json::to_value(&ctx.config)?;
+    let changed = |section: &str| previous_value.get(section) != current_value.get(section);
+
+    remove_orphans(ctx, previous)?;
+
+    // the throttle is read by node units, so update it before they restart
+    let mut restart_nodes = false;
+    if changed("bandwidth") {
+        bandwidth::reconfigure(ctx)?;
+        restart_nodes = bandwidth::enforce(ctx)?;
+    }
+    if changed("kaspad") {
+        kaspad::reconfigure(ctx, true)?;
+    } else if restart_nodes {
+        kaspad::restart_all(ctx)?;
+    }
+    if changed("resolver") {
+        resolver::reconfigure(ctx, true)?;
+    }
+    if changed("exporter") {
+        exporter::reconfigure(ctx)?;
+    }
+    if changed("watchdog") {
+        watchdog::reconfigure(ctx)?;
+    }
+    if changed("alerts") {
+        alerts::reconfigure(ctx)?;
+    }
+    if changed("acme") {
+        if ctx.config.acme.enabled {
+            acme::install_renewal_timer(ctx)?;
+        } else {
+            acme::remove_renewal_timer()?;
+        }
+    }
+    // sampled data folders follow node and resolver configs
+    if changed("kaspad") || changed("resolver") {
+        storage::reconfigure(ctx)?;
+    }
+    // proxies follow node and resolver interfaces
+    nginx::reconfigure(ctx)?;
+    Ok(())
+}
+
+pub fn restore(ctx: &mut Context) -> Result<()> {
+    let snapshots = snapshots();
+    if snapshots.is_empty() {
+        log::warning("No configuration snapshots found")?;
+        return Ok(());
+    }
+
+    let mut selector = cliclack::select("Select configuration snapshot to restore (ESC to cancel)");
+    for snapshot in snapshots.iter() {
+        selector = selector.item(snapshot.clone(), snapshot, "");
+    }
+    let Ok(snapshot) = selector.interact() else {
+        return Ok(());
+    };
+
+    let mut config = snapshot.config.clone();
+    let applied = migration::migrate(&mut config)?;
+    if !applied.is_empty() {
+        log::info(format!(
+            "Snapshot will be migrated to config version {}:\n{}",
+            migration::CONFIG_VERSION,
+            applied.join("\n")
+        ))?;
+    }
+
+    let current = serde_json::to_value(&ctx.config)?;
+    if current == config {
+        log::info("Snapshot matches the current configuration")?;
+        return Ok(());
+    }
+    log::info(unified_diff(&current, &config)?)?;
+
+    if !confirm("Restore this configuration and reconfigure services?")
+        .initial_value(false)
+        .interact()?
+    {
+        return Ok(());
+    }
+
+    let previous = std::mem::replace(&mut ctx.config, serde_json::from_value(config)?);
+    ctx.config.save()?;
+    reconfigure(ctx, &previous)?;
+    log::success(format!("Configuration restored from {snapshot}"))?;
+    Ok(())
+}
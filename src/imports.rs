@@ -23,31 +23,50 @@ pub use workflow_encryption::prelude::*;
 pub use workflow_serializer::prelude::*;
 pub use workflow_utils::prelude::{arglist::*, format::*, ip, version};
 
+pub use crate::access;
+pub use crate::acme;
 pub use crate::actions;
+pub use crate::alerts;
 pub use crate::args::*;
+pub use crate::bandwidth;
 pub use crate::base;
 pub use crate::config::Config;
 pub use crate::console::*;
 pub use crate::content::*;
 pub use crate::context::Context;
 pub use crate::error::Error;
+pub use crate::exporter;
 pub use crate::flag;
 pub use crate::folders::*;
 pub use crate::fqdn;
 pub use crate::git::{self, Origin};
+pub use crate::hardware;
+pub use crate::health;
+pub use crate::history;
 pub use crate::kaspad;
 pub use crate::khost;
+pub use crate::migration;
 pub use crate::network::{Interface, Network};
 pub use crate::nginx;
 pub use crate::nginx::ProxyConfig;
+pub use crate::perf;
+pub use crate::pki;
+pub use crate::ports;
 pub use crate::resolver;
 pub use crate::result::{Capture, Result};
 pub use crate::rust;
 pub use crate::service::*;
+pub use crate::sites;
 pub use crate::status;
+pub use crate::storage;
 pub use crate::sudo;
+pub use crate::support;
 pub use crate::system;
 pub use crate::systemd;
+pub use crate::top;
+pub use crate::usage;
 pub use crate::utils::*;
+pub use crate::watchdog;
+pub use crate::wrpc;
 
 pub use crate::*;
@@ -1,6 +1,18 @@
 use crate::imports::*;
 use nginx::prelude::*;
 
+/// Kaspa gRPC service path (shared by all networks)
+const GRPC_SERVICE_PATH: &str = "/protowire.RPC/";
+
+/// Port offset between node instances serving the same network
+const INSTANCE_PORT_OFFSET: u16 = 10;
+/// Maximum number of additional node instances per network
+pub const MAX_INSTANCES: u16 = 9;
+
+/// Unit environment variable holding the incoming peer limit arguments,
+/// overridden by the bandwidth throttle environment file
+pub const PEER_ARGS: &str = "KHOST_PEER_ARGS";
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Config {
     enabled: bool,
@@ -10,6 +22,9 @@ pub struct Config {
     perf_metrics_log: bool,
     origin: Origin,
     network: Network,
+    /// Node instance number (0 is the primary instance)
+    #[serde(default)]
+    instance: u16,
     data_folder: Option<PathBuf>,
     enable_upnp: bool,
     outgoing_peers: Option<u16>,
@@ -20,6 +35,9 @@ pub struct Config {
     wrpc_borsh: Option<Interface>,
     #[serde(skip_serializing_if = "Option::is_none")]
     wrpc_json: Option<Interface>,
+    /// Expose the gRPC interface through NGINX (TLS only)
+    #[serde(default)]
+    expose_grpc: bool,
 }
 
 impl Service for Config {
@@ -28,7 +46,11 @@ impl Service for Config {
     }
 
     fn service_name(&self) -> String {
-        format!("kaspa-{}", self.network)
+        if self.instance == 0 {
+            format!("kaspa-{}", self.network)
+        } else {
+            format!("kaspa-{}-{}", self.network, self.instance)
+        }
     }
 
     fn kind(&self) -> ServiceKind {
@@ -47,7 +69,7 @@ impl Service for Config {
         true
     }
 
-    fn proxy_config(&self, _ctx: &Context) -> Option<Vec<ProxyConfig>> {
+    fn proxy_config(&self, ctx: &Context) -> Option<Vec<ProxyConfig>> {
         let mut proxy_configs = Vec::new();
 
         if let Some(iface) = self.wrpc_borsh.as_ref() {
@@ -72,17 +94,40 @@ impl Service for Config {
             proxy_configs.push(proxy_config);
         }
 
+        if let Some(iface) = self.grpc.as_ref() {
+            if self.expose_grpc && ctx.config.nginx.certs().is_some() {
+                let port = iface.port();
+                let proxy_kind = ProxyKind::grpc(port);
+                let proxy_config = ProxyConfig::new(
+                    format!("{} ({})", self.service_title(), self.service_name()),
+                    GRPC_SERVICE_PATH,
+                    proxy_kind,
+                );
+                proxy_configs.push(proxy_config);
+            }
+        }
+
         Some(proxy_configs)
     }
 }
 
+/// Default gRPC port of a network; p2p (+1) and wRPC (+1000, +2000) ports follow from it
+fn base_port(network: Network) -> u16 {
+    match network {
+        Network::Mainnet => 16110,
+        Network::Testnet10 => 16210,
+        Network::Testnet11 => 16310,
+    }
+}
+
 impl Config {
     pub fn new(origin: Origin, network: Network) -> Self {
-        let (grpc, wrpc_borsh, wrpc_json) = match network {
-            Network::Mainnet => (16110, 17110, 18110),
-            Network::Testnet10 => (16210, 17210, 18210),
-            Network::Testnet11 => (16310, 17310, 18310),
-        };
+        Self::new_instance(origin, network, 0)
+    }
+
+    pub fn new_instance(origin: Origin, network: Network, instance: u16) -> Self {
+        let grpc = base_port(network) + instance * INSTANCE_PORT_OFFSET;
+        let (wrpc_borsh, wrpc_json) = (grpc + 1000, grpc + 2000);
 
         Self {
             enabled: false,
@@ -90,6 +135,7 @@ impl Config {
             perf_metrics_log: false,
             origin,
             network,
+            instance,
             data_folder: None,
             enable_upnp: false,
             outgoing_peers: Some(32),
@@ -97,6 +143,7 @@ impl Config {
             grpc: Some(Interface::Local(grpc)),
             wrpc_borsh: Some(Interface::Local(wrpc_borsh)),
             wrpc_json: Some(Interface::Local(wrpc_json)),
+            expose_grpc: false,
         }
     }
 
@@ -105,15 +152,80 @@ impl Config {
     }
 
     pub fn data_folder(&self) -> PathBuf {
-        self.data_folder
-            .clone()
-            .unwrap_or_else(|| home_folder().join(".rusty-kaspa").join(self.service_name()))
+        match (&self.data_folder, self.appdir()) {
+            (Some(data_folder), _) => data_folder.clone(),
+            (None, Some(appdir)) => appdir.join(format!("kaspa-{}", self.network)),
+            (None, None) => home_folder().join(".rusty-kaspa").join(self.service_name()),
+        }
+    }
+
+    /// Application folder passed to kaspad via `--appdir`
+    fn appdir(&self) -> Option<PathBuf> {
+        self.data_folder.clone().or_else(|| {
+            (self.instance > 0).then(|| {
+                home_folder()
+                    .join(".rusty-kaspa")
+                    .join(format!("instance-{}", self.instance))
+            })
+        })
+    }
+
+    pub fn instance(&self) -> u16 {
+        self.instance
+    }
+
+    /// p2p port the node listens on
+    fn listen_port(&self) -> u16 {
+        base_port(self.network) + 1 + self.instance * INSTANCE_PORT_OFFSET
+    }
+
+    /// p2p listen port for additional instances (the primary uses the kaspad default)
+    fn p2p_port(&self) -> Option<u16> {
+        (self.instance > 0).then(|| self.listen_port())
+    }
+
+    /// Ports this node listens on, labelled by interface
+    pub fn ports(&self) -> Vec<(&'static str, u16)> {
+        let mut ports = vec![("p2p", self.listen_port())];
+        for (label, interface) in [
+            ("gRPC", &self.grpc),
+            ("wRPC borsh", &self.wrpc_borsh),
+            ("wRPC json", &self.wrpc_json),
+        ] {
+            if let Some(interface) = interface {
+                ports.push((label, interface.port()));
+            }
+        }
+        ports
     }
 
     pub fn network(&self) -> Network {
         self.network
     }
 
+    /// Local address of the wRPC (json) interface
+    pub fn wrpc_json_address(&self) -> Option<std::net::SocketAddr> {
+        self.wrpc_json
+            .as_ref()
+            .map(|iface| std::net::SocketAddr::from(([127, 0, 0, 1], iface.port())))
+    }
+
+    pub fn max_incoming_peers(&self) -> Option<u16> {
+        self.max_incoming_peers
+    }
+
+    pub fn set_max_incoming_peers(&mut self, max_incoming_peers: Option<u16>) {
+        self.max_incoming_peers = max_incoming_peers;
+    }
+
+    pub fn perf_metrics_log(&self) -> bool {
+        self.perf_metrics_log
+    }
+
+    pub fn set_perf_metrics_log(&mut self, perf_metrics_log: bool) {
+        self.perf_metrics_log = perf_metrics_log;
+    }
+
     pub fn enable(&mut self) {
         self.enabled = true;
     }
@@ -166,9 +278,8 @@ impl From<&Config> for Vec<String> {
             args.push(format!("--outpeers={outgoing_peers}"));
         }
 
-        if let Some(max_incoming_peers) = config.max_incoming_peers {
-            args.push(format!("--maxinpeers={max_incoming_peers}"));
-        }
+        // incoming peer limit is passed via `PEER_ARGS` (see `create_systemd_unit`)
+        args.push(format!("${PEER_ARGS}"));
 
         if let Some(interface) = &config.grpc {
             args.push(format!("--rpclisten={interface}"));
@@ -182,8 +293,12 @@ impl From<&Config> for Vec<String> {
             args.push(format!("--rpclisten-json={interface}"));
         }
 
-        if let Some(data_folder) = &config.data_folder {
-            args.push(format!("--appdir={}", data_folder.display()));
+        if let Some(port) = config.p2p_port() {
+            args.push(format!("--listen=0.0.0.0:{port}"));
+        }
+
+        if let Some(appdir) = config.appdir() {
+            args.push(format!("--appdir={}", appdir.display()));
         }
 
         args.into()
@@ -242,13 +357,15 @@ pub fn is_installed(ctx: &Context) -> bool {
 }
 
 pub fn update(ctx: &Context) -> Result<()> {
-    fetch(ctx)?;
-    build(ctx)?;
-    step("Restarting Kaspa p2p nodes...", || {
-        for config in active_configs(ctx) {
-            systemd::restart(config)?;
-        }
-        Ok(())
+    alerts::on_error(ctx, alerts::Kind::UpdateFailed, "kaspad", || {
+        fetch(ctx)?;
+        build(ctx)?;
+        step("Restarting Kaspa p2p nodes...", || {
+            for config in active_configs(ctx) {
+                systemd::restart(config)?;
+            }
+            Ok(())
+        })
     })?;
     log::success("Update successful")?;
     Ok(())
@@ -363,13 +480,20 @@ pub fn version(origin: &Origin) -> Option<String> {
         .and_then(|s| {
             s.trim()
                 .split(' ')
-                .last()
+                .next_back()
                 .map(|version| format!("{version}-{hash}"))
         })
 }
 
 pub fn create_systemd_unit(ctx: &Context, config: &Config) -> Result<()> {
-    let description = format!("Kaspad p2p Node ({})", config.network);
+    let description = if config.instance == 0 {
+        format!("Kaspad p2p Node ({})", config.network)
+    } else {
+        format!(
+            "Kaspad p2p Node ({} instance {})",
+            config.network, config.instance
+        )
+    };
 
     let args = Vec::<String>::from(config);
     let exec_start = [binary(&config.origin).display().to_string()]
@@ -377,37 +501,41 @@ pub fn create_systemd_unit(ctx: &Context, config: &Config) -> Result<()> {
         .chain(args)
         .collect::<Vec<_>>();
 
-    let unit_config = systemd::Config::new(config, description, &ctx.username, exec_start, 5);
+    let unit_config = systemd::Config::new(config, description, &ctx.username, exec_start, 5)
+        .with_environment(PEER_ARGS, peer_args(config.max_incoming_peers))
+        .with_environment_file(bandwidth::throttle_filename(&config.service_name()));
 
     systemd::create(unit_config)?;
     Ok(())
 }
 
-pub fn supports_multiple_networks(ctx: &Context, networks: usize) -> bool {
-    // 16+14+12; 16+12
-    let limits = [(3, 40), (2, 30)].iter();
-    for (nodes, limit) in limits {
-        if networks >= *nodes && ctx.system.ram_as_gb() <= (*limit - 2) {
-            return false;
-        }
-    }
-
-    true
+/// kaspad arguments for an incoming peer limit
+pub fn peer_args(max_incoming_peers: Option<u16>) -> String {
+    max_incoming_peers
+        .map(|peers| format!("--maxinpeers={peers}"))
+        .unwrap_or_default()
 }
 
 pub fn configure_networks(ctx: &mut Context, networks: Vec<Network>) -> Result<()> {
     let networks = networks.into_iter().collect::<HashSet<_>>();
-    let limits = [(3, 42), (2, 32)].iter();
-    for (nodes, limit) in limits {
-        if networks.len() >= *nodes && ctx.system.ram_as_gb() <= (*limit - 2) {
-            log::error(format!(
-                "Detected RAM is {}, minimum required for {} networks is {} Gb. Aborting...",
-                as_gb(ctx.system.total_memory as f64, false, false),
-                nodes,
-                *limit
-            ))?;
-            return Ok(());
-        }
+    // one node per config to be enabled, instances included (as in `hardware::conflicts`)
+    let nodes = ctx
+        .config
+        .kaspad
+        .iter()
+        .filter(|config| networks.contains(&config.network))
+        .map(Config::network)
+        .collect::<Vec<_>>();
+    let conflicts = hardware::Assessment::detect(ctx, false).check_networks(ctx, &nodes);
+    for conflict in conflicts.iter() {
+        conflict.render()?;
+    }
+    if conflicts
+        .iter()
+        .any(|conflict| matches!(conflict, status::Conflict::Error(_)))
+    {
+        log::error("Insufficient resources for the selected networks. Aborting...")?;
+        return Ok(());
     }
 
     for config in ctx.config.kaspad.iter_mut() {
@@ -578,11 +706,9 @@ pub fn find_config_by_service_detail<'a>(
 }
 
 pub fn select_networks(ctx: &mut Context) -> Result<()> {
-    if ctx.system.ram_as_gb() < 24 {
-        log::warning(format!(
-            "Detected RAM is {}, minimum required for multiple networks is 32 Gb.",
-            as_gb(ctx.system.total_memory as f64, false, false)
-        ))?;
+    let assessment = hardware::Assessment::detect(ctx, false);
+    if !assessment.supports_multiple_networks() {
+        log::warning("Detected RAM is insufficient for multiple networks")?;
 
         let mut selector = cliclack::select("Select Kaspa p2p node network to enable");
         let details = ctx
@@ -624,15 +750,22 @@ pub fn select_networks(ctx: &mut Context) -> Result<()> {
         }
         let selected = selector.interact()?;
 
-        if !supports_multiple_networks(ctx, selected.len()) {
-            log::error(format!(
-                "Detected RAM of {} is insufficient for {} networks",
-                as_gb(ctx.system.total_memory as f64, false, false),
-                selected.len()
-            ))?;
-            if !confirm("Do you want to proceed?").interact()? {
-                return Err(Error::UserAbort);
-            }
+        let networks = selected
+            .iter()
+            .filter_map(|detail| {
+                ctx.config
+                    .kaspad
+                    .iter()
+                    .find(|config| config.service_name() == detail.name)
+                    .map(Config::network)
+            })
+            .collect::<Vec<_>>();
+        let conflicts = assessment.check_networks(ctx, &networks);
+        for conflict in conflicts.iter() {
+            conflict.render()?;
+        }
+        if !conflicts.is_empty() && !confirm("Do you want to proceed?").interact()? {
+            return Err(Error::UserAbort);
         }
 
         ctx.config.kaspad.iter_mut().for_each(Config::disable);
@@ -642,3 +775,153 @@ pub fn select_networks(ctx: &mut Context) -> Result<()> {
     }
     Ok(())
 }
+
+pub fn configure_grpc(ctx: &mut Context) -> Result<()> {
+    if ctx.config.nginx.certs().is_none() {
+        log::warning("gRPC can only be exposed when SSL certificates are configured")?;
+        return Ok(());
+    }
+
+    let mut selector = cliclack::select("Select network to expose gRPC interface over TLS");
+    let exposed = ctx
+        .config
+        .kaspad
+        .iter()
+        .find(|config| config.is_enabled() && config.expose_grpc)
+        .map(|config| config.network);
+    if let Some(exposed) = exposed {
+        selector = selector.initial_value(Some(exposed));
+    }
+    for config in active_configs(ctx) {
+        selector = selector.item(
+            Some(config.network),
+            config.service_detail(),
+            config
+                .grpc
+                .as_ref()
+                .map(ToString::to_string)
+                .unwrap_or_default(),
+        );
+    }
+    selector = selector.item(None, "Disable gRPC exposure", "");
+
+    log::info(format!(
+        "All networks share the gRPC service path `{GRPC_SERVICE_PATH}`,\nonly one network can be exposed per host."
+    ))?;
+
+    let selected = selector.interact()?;
+    for config in ctx.config.kaspad.iter_mut() {
+        config.expose_grpc = Some(config.network) == selected && config.grpc.is_some();
+    }
+    ctx.config.save()?;
+
+    Ok(())
+}
+
+pub fn add_instance(ctx: &mut Context) -> Result<()> {
+    let mut selector = cliclack::select("Select network for the additional node instance");
+    for network in Network::iter() {
+        selector = selector.item(*network, network, "");
+    }
+    let network = selector.interact()?;
+
+    let instances = ctx
+        .config
+        .kaspad
+        .iter()
+        .filter(|config| config.network == network)
+        .map(|config| config.instance)
+        .collect::<HashSet<_>>();
+    let Some(instance) = (1..=MAX_INSTANCES).find(|instance| !instances.contains(instance)) else {
+        return Err(Error::custom(format!(
+            "Maximum number of {network} node instances reached"
+        )));
+    };
+
+    let origin = ctx
+        .config
+        .kaspad
+        .iter()
+        .find(|config| config.network == network)
+        .map(|config| config.origin.clone())
+        .ok_or(Error::NotFound)?;
+
+    let mut config = Config::new_instance(origin, network, instance);
+    config.enable();
+    log::info(format!(
+        "Adding '{}' (wRPC borsh {}, json {})",
+        config.service_name(),
+        config
+            .wrpc_borsh
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+        config
+            .wrpc_json
+            .as_ref()
+            .map(ToString::to_string)
+            .unwrap_or_default(),
+    ))?;
+    ctx.config.kaspad.push(config);
+    ctx.config.save()?;
+
+    reconfigure(ctx, false)?;
+    // watch the new instance and install its `OnFailure=` drop-in
+    watchdog::reconfigure(ctx)?;
+    alerts::reconfigure(ctx)?;
+
+    Ok(())
+}
+
+pub fn remove_instance(ctx: &mut Context) -> Result<()> {
+    let instances = ctx
+        .config
+        .kaspad
+        .iter()
+        .filter(|config| config.instance > 0)
+        .map(Service::service_detail)
+        .collect::<Vec<_>>();
+
+    if instances.is_empty() {
+        log::warning("No additional node instances are configured")?;
+        return Ok(());
+    }
+
+    let mut selector = cliclack::select("Select node instance to remove");
+    for detail in instances {
+        selector = selector.item(detail.clone(), detail, "");
+    }
+    let selected = selector.interact()?;
+
+    if let Some(config) = find_config_by_service_detail(ctx, &selected) {
+        let config = config.clone();
+        if systemd::exists(&config) {
+            step(format!("Removing service '{}'", selected.name), || {
+                systemd::stop(&config)?;
+                systemd::disable(&config)?;
+                systemd::remove(&config)?;
+                systemd::daemon_reload()
+            })?;
+        }
+
+        let data_folder = config.data_folder();
+        if data_folder.exists()
+            && confirm(format!("Remove data folder '{}'?", data_folder.display()))
+                .initial_value(false)
+                .interact()?
+        {
+            purge_data_folder(&config)?;
+        }
+    }
+
+    alerts::remove_dropin(&selected.name)?;
+    ctx.config
+        .kaspad
+        .retain(|config| config.service_name() != selected.name);
+    ctx.config.save()?;
+
+    watchdog::reconfigure(ctx)?;
+    alerts::reconfigure(ctx)?;
+
+    Ok(())
+}
@@ -59,6 +59,11 @@ pub fn reconfigure_if_needed(ctx: &mut Context, _services_updated: bool) -> Resu
         log::warning("Service configuration rebuild is required...")?;
         kaspad::reconfigure(ctx, true)?;
         resolver::reconfigure(ctx, true)?;
+        exporter::reconfigure(ctx)?;
+        watchdog::reconfigure(ctx)?;
+        alerts::reconfigure(ctx)?;
+        storage::reconfigure(ctx)?;
+        bandwidth::reconfigure(ctx)?;
         nginx::reconfigure(ctx)?;
     }
 
@@ -1,29 +1,48 @@
+pub mod access;
+pub mod acme;
 pub mod actions;
+pub mod alerts;
 pub mod args;
+pub mod bandwidth;
 pub mod base;
 pub mod config;
 pub mod console;
 pub mod content;
 pub mod context;
 pub mod error;
+pub mod exporter;
 pub mod flag;
 pub mod folders;
 pub mod fqdn;
 pub mod git;
+pub mod hardware;
+pub mod health;
+pub mod history;
 pub mod imports;
 pub mod kaspad;
 pub mod khost;
+pub mod migration;
 pub mod network;
 pub mod nginx;
+pub mod perf;
+pub mod pki;
+pub mod ports;
 pub mod resolver;
 pub mod result;
 pub mod rust;
 pub mod service;
+pub mod sites;
 pub mod status;
+pub mod storage;
+pub mod support;
 pub mod system;
 pub mod systemd;
 pub mod tls;
+pub mod top;
+pub mod usage;
 pub mod utils;
+pub mod watchdog;
+pub mod wrpc;
 #[macro_use]
 pub mod cmd;
 pub use cmd::*;
@@ -32,6 +51,16 @@ pub mod sudo;
 
 use crate::imports::*;
 
+/// Load the context; exits if the config can not be loaded (e.g. newer than this binary)
+fn init_context(args: Args) -> Context {
+    Context::try_new(args).unwrap_or_else(|err| {
+        let _ = log::error(err);
+        let _ = outro("Exiting...");
+        println!();
+        std::process::exit(1);
+    })
+}
+
 // #[tokio::main]
 fn main() {
     println!();
@@ -54,11 +83,35 @@ fn main() {
         std::process::exit(2);
     }
 
+    if let Some(command) = args.command.clone() {
+        let mut ctx = init_context(args);
+        let result = match command {
+            args::Command::Alert { action } => match action {
+                args::AlertAction::UnitFailed { unit } => alerts::unit_failed(&ctx, &unit),
+                args::AlertAction::Check => alerts::check(&ctx),
+                args::AlertAction::Test => alerts::test(&ctx),
+            },
+            args::Command::Bandwidth => bandwidth::run(&mut ctx),
+            args::Command::Exporter { listen } => exporter::run(&ctx, listen),
+            args::Command::Storage => storage::record(&ctx),
+            args::Command::Top => {
+                sudo::init(&mut ctx);
+                top::run(&ctx)
+            }
+            args::Command::Watchdog => watchdog::run(&ctx),
+        };
+        if let Err(err) = result {
+            println!("{err}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     // Check for updates
     khost::update().ok();
 
     // init context & load khost config
-    let mut ctx = Context::try_new(args).unwrap();
+    let mut ctx = init_context(args);
 
     sudo::init(&mut ctx);
 
@@ -0,0 +1,127 @@
+use crate::imports::*;
+use serde_json::Value;
+
+type Migration = fn(&mut Value) -> Result<()>;
+
+/// Ordered config migrations; entry `n` upgrades version `n + 1` to `n + 2`
+const MIGRATIONS: &[(&str, Migration)] = &[("rename `omega` node branch to `pnn-v1`", v1_to_v2)];
+
+/// Config version written by this binary
+pub const CONFIG_VERSION: u64 = MIGRATIONS.len() as u64 + 1;
+
+fn v1_to_v2(config: &mut Value) -> Result<()> {
+    let nodes = config
+        .get_mut("kaspad")
+        .and_then(Value::as_array_mut)
+        .ok_or_else(|| Error::custom("missing `kaspad` section"))?;
+    for node in nodes.iter_mut() {
+        if let Some(branch) = node.pointer_mut("/origin/branch") {
+            if branch == "omega" {
+                *branch = Value::from("pnn-v1");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Version of a raw config (configs predating versioning are version 1)
+pub fn version(config: &Value) -> u64 {
+    config
+        .get("version")
+        .and_then(Value::as_u64)
+        .unwrap_or(1)
+        .max(1)
+}
+
+/// Upgrade a raw config to [`CONFIG_VERSION`]; returns the applied migration descriptions
+pub fn migrate(config: &mut Value) -> Result<Vec<String>> {
+    let from = version(config);
+    if from > CONFIG_VERSION {
+        return Err(Error::custom(format!(
+            "Config version {from} is newer than supported by kHOST {} (version {CONFIG_VERSION}); please update kHOST",
+            khost::version()
+        )));
+    }
+
+    let mut applied = Vec::new();
+    for (index, (description, migration)) in MIGRATIONS.iter().enumerate().skip(from as usize - 1) {
+        let (from, to) = (index + 1, index + 2);
+        migration(config).map_err(|err| {
+            Error::custom(format!("Config migration v{from} to v{to} failed: {err}"))
+        })?;
+        config["version"] = Value::from(to);
+        applied.push(format!("v{from} to v{to}: {description}"));
+    }
+    Ok(applied)
+}
+
+/// Copy of the config file kept before writing a migrated config
+pub fn backup_filename(path: &Path, version: u64) -> PathBuf {
+    path.with_extension(format!("v{version}.json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1: &str = include_str!("../tests/fixtures/config-v1.json");
+    const V2: &str = include_str!("../tests/fixtures/config-v2.json");
+
+    fn branches(config: &Value) -> Vec<Option<&str>> {
+        config["kaspad"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|node| node["origin"]["branch"].as_str())
+            .collect()
+    }
+
+    #[test]
+    fn migrates_v1_to_current() {
+        let mut config: Value = serde_json::from_str(V1).unwrap();
+        let applied = migrate(&mut config).unwrap();
+        assert_eq!(applied.len(), MIGRATIONS.len());
+        assert_eq!(version(&config), CONFIG_VERSION);
+        assert_eq!(
+            branches(&config),
+            [Some("pnn-v1"), Some("pnn-v1"), Some("tn11")]
+        );
+        let config: Config = serde_json::from_value(config).unwrap();
+        assert_eq!(config.version, CONFIG_VERSION);
+    }
+
+    #[test]
+    fn current_config_is_unchanged() {
+        let original: Value = serde_json::from_str(V2).unwrap();
+        let mut config = original.clone();
+        assert!(migrate(&mut config).unwrap().is_empty());
+        assert_eq!(config, original);
+        serde_json::from_value::<Config>(config).unwrap();
+    }
+
+    #[test]
+    fn unversioned_config_is_v1() {
+        let mut config: Value = serde_json::from_str(V1).unwrap();
+        config.as_object_mut().unwrap().remove("version");
+        assert_eq!(version(&config), 1);
+        migrate(&mut config).unwrap();
+        assert_eq!(version(&config), CONFIG_VERSION);
+    }
+
+    #[test]
+    fn rejects_newer_config() {
+        let mut config: Value = serde_json::from_str(V2).unwrap();
+        config["version"] = Value::from(CONFIG_VERSION + 1);
+        let err = migrate(&mut config).unwrap_err().to_string();
+        assert!(err.contains("newer"), "{err}");
+    }
+
+    #[test]
+    fn reports_failed_migration() {
+        let mut config: Value = serde_json::from_str(V1).unwrap();
+        config.as_object_mut().unwrap().remove("kaspad");
+        let err = migrate(&mut config).unwrap_err().to_string();
+        assert!(err.contains("v1 to v2"), "{err}");
+        assert_eq!(version(&config), 1);
+    }
+}
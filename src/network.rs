@@ -26,6 +26,29 @@ impl Interface {
     }
 }
 
+/// Validate an IP address or CIDR network (e.g. `10.0.0.0/8`)
+pub fn validate_cidr(cidr: &str) -> Result<()> {
+    let (addr, prefix) = match cidr.split_once('/') {
+        Some((addr, prefix)) => (addr, Some(prefix)),
+        None => (cidr, None),
+    };
+
+    let max = match addr.parse::<std::net::IpAddr>() {
+        Ok(std::net::IpAddr::V4(_)) => 32,
+        Ok(std::net::IpAddr::V6(_)) => 128,
+        Err(_) => return Err(Error::custom(format!("Invalid IP address: `{cidr}`"))),
+    };
+
+    if let Some(prefix) = prefix {
+        match prefix.parse::<u8>() {
+            Ok(prefix) if prefix <= max => {}
+            _ => return Err(Error::custom(format!("Invalid network prefix: `{cidr}`"))),
+        }
+    }
+
+    Ok(())
+}
+
 #[derive(Default, Describe, Clone, Copy, Debug, Serialize, Deserialize, Eq, PartialEq, Hash)]
 #[serde(rename_all = "lowercase")]
 pub enum Network {
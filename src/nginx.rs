@@ -1,9 +1,10 @@
 // use std::io::Read;
 
 use crate::imports::*;
+use access::Access;
 
 pub mod prelude {
-    pub use super::{Certs, NginxConfig, ProxyConfig, ProxyKind, ServerKind};
+    pub use super::{Certs, Limits, NginxConfig, ProxyConfig, ProxyKind, ServerKind};
 }
 
 const NGINX_CONFIG_PATH: &str = "/etc/nginx/";
@@ -23,6 +24,15 @@ pub fn nginx_service_detail() -> ServiceDetail {
 #[derive(Default, Clone, Debug, Serialize, Deserialize)]
 pub struct Config {
     certs: Option<Certs>,
+    /// Rate and connection limit overrides keyed by proxy location path
+    #[serde(default)]
+    limits: HashMap<String, Limits>,
+    /// Access restrictions keyed by proxy location path
+    #[serde(default)]
+    access: HashMap<String, Access>,
+    /// Send `Strict-Transport-Security` header when TLS is enabled
+    #[serde(default)]
+    hsts: bool,
 }
 
 impl Service for Config {
@@ -67,6 +77,89 @@ impl Config {
     pub fn enable_certs(&mut self, certs: Certs) {
         self.certs = Some(certs);
     }
+
+    /// Limits applied to the proxy location (configured override or defaults)
+    pub fn limits(&self, proxy: &ProxyConfig) -> Limits {
+        self.limits
+            .get(&proxy.path)
+            .cloned()
+            .unwrap_or_else(|| Limits::new(&proxy.proxy_kind))
+    }
+
+    pub fn set_limits(&mut self, path: &str, limits: Limits) {
+        self.limits.insert(path.to_string(), limits);
+    }
+
+    pub fn reset_limits(&mut self, path: &str) {
+        self.limits.remove(path);
+    }
+
+    pub fn hsts(&self) -> bool {
+        self.hsts
+    }
+
+    pub fn set_hsts(&mut self, hsts: bool) {
+        self.hsts = hsts;
+    }
+
+    pub fn access(&self, path: &str) -> Access {
+        self.access.get(path).cloned().unwrap_or_default()
+    }
+
+    pub fn set_access(&mut self, path: &str, access: Access) {
+        if access.is_enabled() {
+            self.access.insert(path.to_string(), access);
+        } else {
+            self.access.remove(path);
+        }
+    }
+}
+
+/// Per-client (remote address) request rate and concurrency limits
+#[derive(Clone, Debug, Serialize, Deserialize, Eq, PartialEq)]
+pub struct Limits {
+    /// Requests per second (for wRPC this limits websocket handshakes)
+    pub rate: Option<u32>,
+    /// Requests allowed in excess of the rate
+    pub burst: u32,
+    /// Concurrent connections
+    pub connections: Option<u32>,
+}
+
+impl Limits {
+    /// Public node defaults
+    pub fn new(proxy_kind: &ProxyKind) -> Self {
+        match proxy_kind {
+            ProxyKind::Http { .. } => Self {
+                rate: Some(20),
+                burst: 40,
+                connections: Some(32),
+            },
+            ProxyKind::Wrpc { .. } | ProxyKind::Grpc { .. } => Self {
+                rate: Some(5),
+                burst: 10,
+                connections: Some(16),
+            },
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.rate.is_some() || self.connections.is_some()
+    }
+}
+
+impl Display for Limits {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let rate = self
+            .rate
+            .map(|rate| format!("{rate} r/s (burst {})", self.burst))
+            .unwrap_or("unlimited".to_string());
+        let connections = self
+            .connections
+            .map(|connections| connections.to_string())
+            .unwrap_or("unlimited".to_string());
+        write!(f, "rate: {rate}, connections: {connections}")
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -162,8 +255,16 @@ impl ServerKind {
 
 #[derive(Debug, Clone)]
 pub enum ProxyKind {
-    Http { port: u16 },
-    Wrpc { port: u16 },
+    Http {
+        port: u16,
+    },
+    Wrpc {
+        port: u16,
+    },
+    /// gRPC over HTTP/2 (requires TLS)
+    Grpc {
+        port: u16,
+    },
 }
 
 impl ProxyKind {
@@ -174,6 +275,16 @@ impl ProxyKind {
     pub fn wrpc(port: u16) -> Self {
         Self::Wrpc { port }
     }
+
+    pub fn grpc(port: u16) -> Self {
+        Self::Grpc { port }
+    }
+
+    pub fn port(&self) -> u16 {
+        match self {
+            Self::Http { port } | Self::Wrpc { port } | Self::Grpc { port } => *port,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -181,6 +292,10 @@ pub struct ProxyConfig {
     pub title: String,
     pub path: String,
     pub proxy_kind: ProxyKind,
+    pub limits: Option<Limits>,
+    pub access: Option<Access>,
+    /// Local ports of all instances serving this location
+    pub upstream: Vec<u16>,
 }
 
 impl ProxyConfig {
@@ -193,13 +308,64 @@ impl ProxyConfig {
             title: title.to_string(),
             path: path.to_string(),
             proxy_kind,
+            limits: None,
+            access: None,
+            upstream: vec![],
+        }
+    }
+
+    pub fn with_limits(mut self, limits: Limits) -> Self {
+        self.limits = limits.is_enabled().then_some(limits);
+        self
+    }
+
+    pub fn with_access(mut self, access: Access) -> Self {
+        self.access = access.is_enabled().then_some(access);
+        self
+    }
+
+    /// Name of the NGINX upstream block when load balancing across instances
+    pub fn upstream_name(&self) -> Option<String> {
+        (self.upstream.len() > 1).then(|| format!("{}_upstream", self.zone()))
+    }
+
+    /// Name of the NGINX shared memory zone for this location
+    pub fn zone(&self) -> String {
+        let name = self
+            .path
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect::<String>();
+        let name = name.trim_matches('_');
+        if name.is_empty() {
+            "khost_root".to_string()
+        } else {
+            format!("khost_{name}")
         }
     }
 }
 
+/// Mozilla "intermediate" TLS cipher suite (TLSv1.2; TLSv1.3 suites are not configurable)
+const SSL_CIPHERS: &str = "ECDHE-ECDSA-AES128-GCM-SHA256:ECDHE-RSA-AES128-GCM-SHA256:ECDHE-ECDSA-AES256-GCM-SHA384:ECDHE-RSA-AES256-GCM-SHA384:ECDHE-ECDSA-CHACHA20-POLY1305:ECDHE-RSA-CHACHA20-POLY1305:DHE-RSA-AES128-GCM-SHA256:DHE-RSA-AES256-GCM-SHA384";
+
+/// HSTS max-age (2 years)
+const HSTS_MAX_AGE: u64 = 63072000;
+
+/// Failed attempts before an upstream instance is considered unavailable
+const UPSTREAM_MAX_FAILS: u32 = 3;
+/// Period an unavailable upstream instance is excluded for
+const UPSTREAM_FAIL_TIMEOUT: &str = "30s";
+
+/// Dedicated access log for the kHOST server block
+pub const ACCESS_LOG: &str = "/var/log/nginx/khost-access.log";
+/// Name of the structured (JSON lines) access log format
+const ACCESS_LOG_FORMAT: &str = "khost_json";
+
 pub struct NginxConfig {
     pub server_kind: ServerKind,
     pub proxy_config: Vec<ProxyConfig>,
+    pub acme_webroot: Option<PathBuf>,
+    pub hsts: bool,
 }
 
 impl NginxConfig {
@@ -207,7 +373,71 @@ impl NginxConfig {
         Self {
             server_kind,
             proxy_config,
+            acme_webroot: None,
+            hsts: false,
+        }
+    }
+
+    pub fn with_acme_challenge<P: AsRef<Path>>(mut self, webroot: P) -> Self {
+        self.acme_webroot = Some(webroot.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn with_hsts(mut self, hsts: bool) -> Self {
+        self.hsts = hsts;
+        self
+    }
+
+    fn fmt_acme_challenge(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if let Some(webroot) = &self.acme_webroot {
+            writeln!(f, "\t# ACME HTTP-01 challenge")?;
+            writeln!(f, "\tlocation ^~ /.well-known/acme-challenge/ {{")?;
+            writeln!(f, "\t\troot {};", webroot.display())?;
+            writeln!(f, "\t\tdefault_type \"text/plain\";")?;
+            writeln!(f, "\t}}")?;
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+
+    /// Upstream blocks for locations served by multiple local instances
+    fn fmt_upstreams(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for proxy in self.proxy_config.iter() {
+            if let Some(upstream) = proxy.upstream_name() {
+                writeln!(f, "# {}", proxy.path)?;
+                writeln!(f, "upstream {upstream} {{")?;
+                writeln!(f, "\tleast_conn;")?;
+                for port in proxy.upstream.iter() {
+                    writeln!(
+                        f,
+                        "\tserver 127.0.0.1:{port} max_fails={UPSTREAM_MAX_FAILS} fail_timeout={UPSTREAM_FAIL_TIMEOUT};"
+                    )?;
+                }
+                writeln!(f, "}}")?;
+                writeln!(f)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Plain HTTP server redirecting to HTTPS (ACME challenges are still served)
+    fn fmt_redirect(&self, f: &mut Formatter<'_>, port: u16, fqdns: &[String]) -> fmt::Result {
+        writeln!(f, "server {{")?;
+        writeln!(f, "\tlisten 80;")?;
+        writeln!(f, "\tlisten [::]:80;")?;
+        writeln!(f, "\tserver_name {};", fqdn::flatten(fqdns))?;
+        writeln!(f)?;
+        self.fmt_acme_challenge(f)?;
+        writeln!(f, "\tlocation / {{")?;
+        if port == 443 {
+            writeln!(f, "\t\treturn 301 https://$host$request_uri;")?;
+        } else {
+            writeln!(f, "\t\treturn 301 https://$host:{port}$request_uri;")?;
         }
+        writeln!(f, "\t}}")?;
+        writeln!(f, "}}")?;
+        writeln!(f)?;
+        Ok(())
     }
 }
 
@@ -222,6 +452,13 @@ impl Display for NginxConfig {
         writeln!(f, "#")?;
         writeln!(f, "# Do not edit this file!")?;
         writeln!(f, "#")?;
+
+        self.fmt_upstreams(f)?;
+
+        if let ServerKind::Tls { port, fqdns, .. } = &self.server_kind {
+            self.fmt_redirect(f, port.unwrap_or(443), fqdns)?;
+        }
+
         writeln!(f, "server {{")?;
 
         match &self.server_kind {
@@ -233,37 +470,118 @@ impl Display for NginxConfig {
             }
             ServerKind::Tls { port, certs, fqdns } => {
                 let port = port.unwrap_or(443);
-                writeln!(f, "\tlisten {port} ssl;")?;
-                writeln!(f, "\tlisten [::]:{port} ssl;")?;
-                writeln!(f, "\tssl_certificate {}", certs.crt)?;
-                writeln!(f, "\tssl_certificate_key {}", certs.key)?;
+                writeln!(f, "\tlisten {port} ssl http2;")?;
+                writeln!(f, "\tlisten [::]:{port} ssl http2;")?;
                 writeln!(f, "\tserver_name {};", fqdn::flatten(fqdns))?;
+                writeln!(f)?;
+                writeln!(f, "\tssl_certificate {};", certs.crt)?;
+                writeln!(f, "\tssl_certificate_key {};", certs.key)?;
+                writeln!(f, "\tssl_protocols TLSv1.2 TLSv1.3;")?;
+                writeln!(f, "\tssl_ciphers {SSL_CIPHERS};")?;
+                writeln!(f, "\tssl_prefer_server_ciphers off;")?;
+                writeln!(f, "\tssl_session_timeout 1d;")?;
+                writeln!(f, "\tssl_session_cache shared:khost_ssl:10m;")?;
+                writeln!(f, "\tssl_session_tickets off;")?;
+                writeln!(f, "\tssl_stapling on;")?;
+                writeln!(f, "\tssl_stapling_verify on;")?;
+                if self.hsts {
+                    writeln!(
+                        f,
+                        "\tadd_header Strict-Transport-Security \"max-age={HSTS_MAX_AGE}\" always;"
+                    )?;
+                }
             }
         }
 
+        writeln!(f)?;
+        writeln!(f, "\taccess_log {ACCESS_LOG} {ACCESS_LOG_FORMAT};")?;
         writeln!(f, "\tclient_max_body_size 1m;")?;
         writeln!(f)?;
 
+        if let ServerKind::Http { .. } = &self.server_kind {
+            self.fmt_acme_challenge(f)?;
+        }
+
         for proxy in self.proxy_config.iter() {
+            let zone = proxy.zone();
+            let upstream = proxy.upstream_name();
+            let target = upstream
+                .clone()
+                .unwrap_or_else(|| format!("127.0.0.1:{}", proxy.proxy_kind.port()));
             let ProxyConfig {
                 title,
                 path,
                 proxy_kind: proxy,
+                limits,
+                access,
+                ..
             } = proxy;
             writeln!(f, "\t# {title}")?;
             writeln!(f, "\tlocation {path} {{")?;
-            writeln!(f, "\t\tproxy_http_version 1.1;")?;
-            writeln!(f, "\t\tproxy_set_header Host $host;")?;
-            writeln!(f, "\t\tproxy_set_header X-Real-IP $remote_addr;")?;
+            if let Some(limits) = limits {
+                if limits.rate.is_some() {
+                    writeln!(
+                        f,
+                        "\t\tlimit_req zone={zone}_req burst={} nodelay;",
+                        limits.burst
+                    )?;
+                    writeln!(f, "\t\tlimit_req_status 429;")?;
+                }
+                if let Some(connections) = limits.connections {
+                    writeln!(f, "\t\tlimit_conn {zone}_conn {connections};")?;
+                    writeln!(f, "\t\tlimit_conn_status 429;")?;
+                }
+            }
+            if let Some(access) = access {
+                for cidr in access.deny.iter() {
+                    writeln!(f, "\t\tdeny {cidr};")?;
+                }
+                for cidr in access.allow.iter() {
+                    writeln!(f, "\t\tallow {cidr};")?;
+                }
+                if !access.allow.is_empty() {
+                    writeln!(f, "\t\tdeny all;")?;
+                }
+                if let Some(auth) = &access.auth {
+                    writeln!(f, "\t\tauth_basic \"{}\";", auth.realm)?;
+                    writeln!(
+                        f,
+                        "\t\tauth_basic_user_file {};",
+                        access::htpasswd_filename(&zone).display()
+                    )?;
+                }
+            }
 
             match proxy {
-                ProxyKind::Http { port } => {
-                    writeln!(f, "\t\tproxy_pass http://127.0.0.1:{port}/;")?;
+                ProxyKind::Http { .. } => {
+                    writeln!(f, "\t\tproxy_http_version 1.1;")?;
+                    writeln!(f, "\t\tproxy_set_header Host $host;")?;
+                    writeln!(f, "\t\tproxy_set_header X-Real-IP $remote_addr;")?;
+                    writeln!(f, "\t\tproxy_pass http://{target}/;")?;
                 }
-                ProxyKind::Wrpc { port } => {
+                ProxyKind::Wrpc { .. } => {
+                    writeln!(f, "\t\tproxy_http_version 1.1;")?;
+                    writeln!(f, "\t\tproxy_set_header Host $host;")?;
+                    writeln!(f, "\t\tproxy_set_header X-Real-IP $remote_addr;")?;
                     writeln!(f, "\t\tproxy_set_header Upgrade $http_upgrade;")?;
                     writeln!(f, "\t\tproxy_set_header Connection \"Upgrade\";")?;
-                    writeln!(f, "\t\tproxy_pass http://127.0.0.1:{port}/;")?;
+                    if upstream.is_some() {
+                        // a websocket stays on the instance it was upgraded on;
+                        // only failed handshakes are retried on another instance
+                        writeln!(f, "\t\tproxy_read_timeout 1h;")?;
+                        writeln!(f, "\t\tproxy_send_timeout 1h;")?;
+                        writeln!(f, "\t\tproxy_next_upstream error timeout;")?;
+                    }
+                    writeln!(f, "\t\tproxy_pass http://{target}/;")?;
+                }
+                ProxyKind::Grpc { .. } => {
+                    // gRPC streams are long-lived; the body size limit
+                    // applies to the entire stream
+                    writeln!(f, "\t\tclient_max_body_size 0;")?;
+                    writeln!(f, "\t\tgrpc_read_timeout 1h;")?;
+                    writeln!(f, "\t\tgrpc_send_timeout 1h;")?;
+                    writeln!(f, "\t\tgrpc_set_header X-Real-IP $remote_addr;")?;
+                    writeln!(f, "\t\tgrpc_pass grpc://{target};")?;
                 }
             }
 
@@ -278,6 +596,56 @@ impl Display for NginxConfig {
     }
 }
 
+/// Shared memory zones referenced by the server block limits and
+/// the access log format, rendered into a separate http-level include.
+pub struct LimitsConfig<'a> {
+    pub proxy_config: &'a [ProxyConfig],
+}
+
+impl Display for LimitsConfig<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "#")?;
+        writeln!(f, "# kHOST v{}", khost::VERSION)?;
+        writeln!(f, "#")?;
+        writeln!(f, "# Do not edit this file!")?;
+        writeln!(f, "#")?;
+
+        writeln!(f, "log_format {ACCESS_LOG_FORMAT} escape=json '{{'")?;
+        writeln!(f, "\t'\"time\":\"$time_iso8601\",'")?;
+        writeln!(f, "\t'\"remote_addr\":\"$remote_addr\",'")?;
+        writeln!(f, "\t'\"method\":\"$request_method\",'")?;
+        writeln!(f, "\t'\"uri\":\"$uri\",'")?;
+        writeln!(f, "\t'\"status\":$status,'")?;
+        writeln!(f, "\t'\"bytes_sent\":$bytes_sent,'")?;
+        writeln!(f, "\t'\"request_length\":$request_length,'")?;
+        writeln!(f, "\t'\"request_time\":$request_time,'")?;
+        writeln!(f, "\t'\"upgrade\":\"$http_upgrade\"'")?;
+        writeln!(f, "'}}';")?;
+        writeln!(f)?;
+
+        for proxy in self.proxy_config.iter() {
+            if let Some(limits) = &proxy.limits {
+                let zone = proxy.zone();
+                writeln!(f, "# {} ({})", proxy.title, proxy.path)?;
+                if let Some(rate) = limits.rate {
+                    writeln!(
+                        f,
+                        "limit_req_zone $binary_remote_addr zone={zone}_req:10m rate={rate}r/s;"
+                    )?;
+                }
+                if limits.connections.is_some() {
+                    writeln!(
+                        f,
+                        "limit_conn_zone $binary_remote_addr zone={zone}_conn:10m;"
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
 pub fn version() -> Option<String> {
     cmd!("nginx", "-v")
         .read()
@@ -285,11 +653,13 @@ pub fn version() -> Option<String> {
         .map(|s| s.trim().to_string())
 }
 
-pub fn install(_ctx: &Context) -> Result<()> {
+pub fn install(ctx: &Context) -> Result<()> {
     step("Setting up NGINX...", || {
         sudo!("apt", "install", "-y", "nginx").run()
     })?;
 
+    sites::resolve(ctx)?;
+
     Ok(())
 }
 
@@ -297,6 +667,24 @@ pub fn reload() -> Result<()> {
     sudo!("nginx", "-s", "reload").run()
 }
 
+/// Validate the active NGINX configuration (`nginx -t`)
+pub fn test() -> Result<()> {
+    let output = sudo!("nginx", "-t")
+        .inner()
+        .stdout_capture()
+        .stderr_capture()
+        .unchecked()
+        .run()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        // `nginx -t` reports diagnostics on stderr
+        Err(Error::NginxConfig(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
 pub fn status(ctx: &Context) -> Result<String> {
     systemd::status(&ctx.config.nginx)
 }
@@ -311,24 +699,207 @@ pub fn config_filename() -> PathBuf {
     PathBuf::from(NGINX_CONFIG_PATH).join("sites-enabled/khost.conf")
 }
 
+pub fn limits_filename() -> PathBuf {
+    PathBuf::from(NGINX_CONFIG_PATH).join("conf.d/khost-limits.conf")
+}
+
+/// Configuration files managed by kHOST
+pub fn config_filenames() -> Vec<PathBuf> {
+    vec![config_filename(), limits_filename()]
+}
+
+fn previous_configs() -> Result<Vec<(PathBuf, Option<String>)>> {
+    config_filenames()
+        .into_iter()
+        .map(|filename| {
+            let previous = if filename.exists() {
+                Some(fs::read_to_string(&filename)?)
+            } else {
+                None
+            };
+            Ok((filename, previous))
+        })
+        .collect()
+}
+
+fn restore_configs(previous: Vec<(PathBuf, Option<String>)>) -> Result<()> {
+    for (filename, previous) in previous {
+        match previous {
+            Some(previous) => sudo::fs::write(filename, previous)?,
+            None => sudo::fs::remove_file(filename)?,
+        }
+    }
+    Ok(())
+}
+
+/// Write the NGINX configuration and validate it with `nginx -t`.
+/// If validation fails, the previous configuration is restored.
 pub fn store(config: NginxConfig) -> Result<()> {
+    let previous = previous_configs()?;
+    let limits = LimitsConfig {
+        proxy_config: &config.proxy_config,
+    };
+    sudo::fs::write(limits_filename(), limits.to_string())?;
     sudo::fs::write(config_filename(), config.to_string())?;
+    if let Err(err) = test() {
+        restore_configs(previous)?;
+        return Err(err);
+    }
+
+    // `nginx -t` does not read htpasswd files; write them only once the
+    // configuration is accepted so a rollback leaves the previous credentials
+    let mut zones = Vec::new();
+    for proxy in config.proxy_config.iter() {
+        if let Some(auth) = proxy
+            .access
+            .as_ref()
+            .and_then(|access| access.auth.as_ref())
+        {
+            access::store_htpasswd(&proxy.zone(), auth)?;
+            zones.push(proxy.zone());
+        }
+    }
+    access::remove_stale_htpasswd(&zones)?;
     Ok(())
 }
 
 pub fn remove() -> Result<()> {
     step("Removing NGINX configuration", || {
-        sudo::fs::remove_file(config_filename())?;
+        let previous = previous_configs()?;
+        for filename in config_filenames() {
+            sudo::fs::remove_file(filename)?;
+        }
+        if let Err(err) = test() {
+            restore_configs(previous)?;
+            return Err(err);
+        }
         reload()?;
-        Ok(())
+        access::remove_stale_htpasswd(&[])
     })
 }
 
 pub fn reconfigure(ctx: &Context) -> Result<()> {
     step("Updating NGINX configuration...", || {
-        let server_kind = ServerKind::new(&ctx.config.nginx.certs).with_fqdn(fqdn::get());
-        let proxy_configs = ctx.proxy_configs(ctx);
-        store(NginxConfig::new(server_kind, proxy_configs))?;
+        let server_kind = ServerKind::new(&ctx.config.nginx.certs).with_fqdn(fqdn::get(ctx));
+        let proxy_configs = balance(ctx.proxy_configs(ctx))
+            .into_iter()
+            .map(|proxy| {
+                let limits = ctx.config.nginx.limits(&proxy);
+                // services may supply their own access rules (see `exporter::proxy_access`)
+                let access = proxy
+                    .access
+                    .clone()
+                    .unwrap_or_else(|| ctx.config.nginx.access(&proxy.path));
+                proxy.with_limits(limits).with_access(access)
+            })
+            .collect();
+        let mut config =
+            NginxConfig::new(server_kind, proxy_configs).with_hsts(ctx.config.nginx.hsts());
+        if ctx.config.acme.enabled {
+            config = config.with_acme_challenge(acme::webroot());
+        }
+        store(config)?;
         reload()
     })
 }
+
+/// Merge proxy configs sharing the same location path (multiple instances
+/// of the same network) into a single load-balanced location.
+pub fn balance(proxy_configs: Vec<ProxyConfig>) -> Vec<ProxyConfig> {
+    let mut balanced: Vec<ProxyConfig> = Vec::new();
+    for proxy in proxy_configs {
+        let port = proxy.proxy_kind.port();
+        match balanced
+            .iter_mut()
+            .find(|existing| existing.path == proxy.path)
+        {
+            Some(existing) => {
+                if !existing.upstream.contains(&port) {
+                    existing.upstream.push(port);
+                }
+            }
+            None => {
+                let mut proxy = proxy;
+                proxy.upstream = vec![port];
+                balanced.push(proxy);
+            }
+        }
+    }
+    balanced
+}
+
+fn ask_limit<S: Display>(prompt: S, current: Option<u32>) -> Result<Option<u32>> {
+    let mut input = cliclack::input(prompt)
+        .required(false)
+        .validate(|input: &String| {
+            if input.trim().is_empty() || input.trim().parse::<u32>().is_ok_and(|v| v > 0) {
+                Ok(())
+            } else {
+                Err("Please enter a positive number or leave empty for unlimited".to_string())
+            }
+        });
+    if let Some(current) = current {
+        input = input.default_input(&current.to_string());
+    }
+    let value: String = input.interact()?;
+    let value = value.trim();
+    Ok((!value.is_empty()).then(|| value.parse().unwrap()))
+}
+
+pub fn configure_limits(ctx: &mut Context) -> Result<()> {
+    let proxy_configs = ctx.proxy_configs(ctx);
+    if proxy_configs.is_empty() {
+        log::warning("No proxied endpoints are configured")?;
+        return Ok(());
+    }
+
+    let mut selector = cliclack::select("Select endpoint to configure limits");
+    for proxy in proxy_configs.iter() {
+        selector = selector.item(
+            proxy.path.clone(),
+            &proxy.path,
+            ctx.config.nginx.limits(proxy),
+        );
+    }
+    let path = selector.interact()?;
+    let proxy = proxy_configs
+        .iter()
+        .find(|proxy| proxy.path == path)
+        .expect("proxy config not found");
+
+    if ctx.config.nginx.limits.contains_key(&path)
+        && confirm("Reset limits to defaults?")
+            .initial_value(false)
+            .interact()?
+    {
+        ctx.config.nginx.reset_limits(&path);
+    } else {
+        let current = ctx.config.nginx.limits(proxy);
+        let rate = ask_limit(
+            "Requests per second per client (empty for unlimited):",
+            current.rate,
+        )?;
+        let burst = if rate.is_some() {
+            ask_limit("Burst (requests above rate):", Some(current.burst))?.unwrap_or(0)
+        } else {
+            0
+        };
+        let connections = ask_limit(
+            "Concurrent connections per client (empty for unlimited):",
+            current.connections,
+        )?;
+        ctx.config.nginx.set_limits(
+            &path,
+            Limits {
+                rate,
+                burst,
+                connections,
+            },
+        );
+    }
+
+    ctx.config.save()?;
+    log::info(format!("{path}: {}", ctx.config.nginx.limits(proxy)))?;
+
+    Ok(())
+}
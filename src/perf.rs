@@ -0,0 +1,247 @@
+use crate::imports::*;
+use chrono::Local;
+use usage::Window;
+
+/// Log target of the kaspad performance monitor
+const PERF_MONITOR: &str = "[perf-monitor] ";
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum Unit {
+    Bytes,
+    BytesPerSec,
+    /// Ratio where 1.0 is one fully utilized core
+    Cpu,
+    Count,
+}
+
+/// Display name and unit of a perf-monitor field; `None` for fields not reported
+fn describe(group: &str, label: &str) -> Option<(String, Unit)> {
+    let known = match label {
+        "RSS" => Some(("resident memory", Unit::Bytes)),
+        "total cpu usage" => Some(("cpu", Unit::Cpu)),
+        "fd" => Some(("file descriptors", Unit::Count)),
+        "read rate" => Some(("disk read", Unit::BytesPerSec)),
+        "write rate" => Some(("disk write", Unit::BytesPerSec)),
+        // static or cumulative values
+        "VIRT" | "cores" | "read" | "write" => return None,
+        _ => None,
+    };
+    Some(match known {
+        Some((name, unit)) => (name.to_string(), unit),
+        // other counters (e.g. RPC) are reported as plain numbers
+        None => (
+            format!("{} {label}", group.trim_end_matches(" metrics")),
+            Unit::Count,
+        ),
+    })
+}
+
+/// Parse a perf-monitor line, e.g.
+/// `[perf-monitor] process metrics: RSS: 123456 (120.56KiB), VIRT: ..., fd: 345`
+pub fn parse_line(line: &str) -> Option<(String, Vec<(String, f64)>)> {
+    let (_, metrics) = line.split_once(PERF_MONITOR)?;
+    let (group, fields) = metrics.split_once(": ")?;
+    let fields = fields
+        .split(", ")
+        .filter_map(|field| {
+            let (label, value) = field.split_once(": ")?;
+            let value = value.split_whitespace().next()?.parse::<f64>().ok()?;
+            Some((label.trim().to_string(), value))
+        })
+        .collect::<Vec<_>>();
+    (!fields.is_empty()).then(|| (group.trim().to_string(), fields))
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Stats {
+    min: f64,
+    max: f64,
+    sum: f64,
+    count: u64,
+}
+
+impl Stats {
+    fn new(value: f64) -> Self {
+        Self {
+            min: value,
+            max: value,
+            sum: value,
+            count: 1,
+        }
+    }
+
+    fn add(&mut self, value: f64) {
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        self.sum += value;
+        self.count += 1;
+    }
+
+    fn avg(&self) -> f64 {
+        self.sum / self.count as f64
+    }
+}
+
+fn format_value(unit: Unit, value: f64) -> String {
+    match unit {
+        Unit::Bytes => as_data_size(value, false),
+        Unit::BytesPerSec => format!("{}/s", as_data_size(value, false)),
+        Unit::Cpu => format!("{:1.1}%", value * 100.0),
+        Unit::Count => format!("{value:1.0}"),
+    }
+}
+
+/// Min/avg/max of perf-monitor fields for a single node
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub service: String,
+    pub samples: u64,
+    metrics: Vec<(String, Unit, Stats)>,
+}
+
+impl Report {
+    pub fn new<S: Display>(service: S, text: &str) -> Self {
+        let mut samples = 0;
+        let mut metrics: Vec<(String, Unit, Stats)> = Vec::new();
+        for (group, fields) in text.lines().filter_map(parse_line) {
+            if group == "process metrics" {
+                samples += 1;
+            }
+            for (label, value) in fields {
+                let Some((name, unit)) = describe(&group, &label) else {
+                    continue;
+                };
+                match metrics.iter_mut().find(|(n, _, _)| *n == name) {
+                    Some((_, _, stats)) => stats.add(value),
+                    None => metrics.push((name, unit, Stats::new(value))),
+                }
+            }
+        }
+        Self {
+            service: service.to_string(),
+            samples,
+            metrics,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.metrics.is_empty()
+    }
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let cells = self
+            .metrics
+            .iter()
+            .map(|(name, unit, stats)| {
+                (
+                    name,
+                    [stats.min, stats.avg(), stats.max].map(|value| format_value(*unit, value)),
+                )
+            })
+            .collect::<Vec<_>>();
+        let width = cells
+            .iter()
+            .flat_map(|(_, values)| values.iter().map(|value| value.len()))
+            .chain([3])
+            .max()
+            .unwrap_or_default();
+        let row = |values: [&str; 3]| {
+            values
+                .iter()
+                .map(|value| value.pad_to_width_with_alignment(width, Alignment::Right))
+                .collect::<Vec<_>>()
+                .join("  ")
+        };
+
+        let mut rows = vec![
+            Content::field("Node:", style(&self.service).cyan().bright()),
+            Content::field("Samples:", self.samples),
+            Content::field("", row(["min", "avg", "max"])),
+        ];
+        for (name, values) in cells.iter() {
+            rows.push(Content::field(
+                format!("{name}:"),
+                row([&values[0], &values[1], &values[2]]),
+            ));
+        }
+        write!(f, "{}", content(rows))
+    }
+}
+
+/// Perf-monitor output of a node within the window
+pub fn read_journal(config: &kaspad::Config, window: Window) -> Result<String> {
+    let since = (Local::now() - window.duration())
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string();
+    sudo!(
+        "journalctl",
+        "-u",
+        config.service_name(),
+        "--since",
+        since,
+        "-o",
+        "cat",
+        "--no-pager"
+    )
+    .read()
+}
+
+fn enable_logging(ctx: &mut Context) -> Result<()> {
+    for config in ctx.config.kaspad.iter_mut() {
+        config.set_perf_metrics_log(true);
+    }
+    ctx.config.save()?;
+    kaspad::reconfigure(ctx, true)?;
+    kaspad::restart_all(ctx)
+}
+
+pub fn report(ctx: &mut Context) -> Result<()> {
+    if kaspad::active_configs(ctx).next().is_none() {
+        log::warning("No active kaspad configurations found")?;
+        return Ok(());
+    }
+
+    let disabled = kaspad::active_configs(ctx)
+        .filter(|config| !config.perf_metrics_log())
+        .map(|config| config.service_name())
+        .collect::<Vec<_>>();
+    if !disabled.is_empty() {
+        log::warning(format!(
+            "Performance metrics logging is disabled for: {}",
+            disabled.join(", ")
+        ))?;
+        if confirm("Enable performance metrics logging (restarts nodes)?")
+            .initial_value(false)
+            .interact()?
+        {
+            enable_logging(ctx)?;
+            log::info(
+                "Performance metrics will be available after the nodes have run for a while",
+            )?;
+            return Ok(());
+        }
+    }
+
+    let window = cliclack::select("Select time window")
+        .item(Window::Hour, Window::Hour, "")
+        .item(Window::Day, Window::Day, "")
+        .item(Window::Week, Window::Week, "")
+        .interact()?;
+
+    for config in kaspad::active_configs(ctx) {
+        let text = read_journal(config, window)?;
+        let report = Report::new(config.service_name(), &text);
+        if report.is_empty() {
+            log::warning(format!(
+                "No performance metrics found for '{}' ({window})",
+                config.service_name()
+            ))?;
+        } else {
+            log::info(report)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,260 @@
+use crate::imports::*;
+use nginx::prelude::*;
+use std::net::IpAddr;
+use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+const CA_VALIDITY_DAYS: u32 = 3650;
+/// Maximum validity accepted by most TLS clients
+const SERVER_VALIDITY_DAYS: u32 = 825;
+
+/// Folder holding the local certificate authority and server certificates
+pub fn folder() -> PathBuf {
+    data_folder().join("pki")
+}
+
+pub fn ca_key() -> PathBuf {
+    folder().join("ca.key")
+}
+
+pub fn ca_crt() -> PathBuf {
+    folder().join("ca.crt")
+}
+
+pub fn certs() -> Certs {
+    Certs::new(folder().join("server.key"), folder().join("server.crt"))
+}
+
+pub fn ca_exists() -> bool {
+    ca_key().is_file() && ca_crt().is_file()
+}
+
+/// Check if the certificates configured in NGINX were issued by the local CA
+pub fn is_managed(ctx: &Context) -> bool {
+    ctx.config
+        .nginx
+        .certs()
+        .is_some_and(|certs| certs.crt == self::certs().crt)
+}
+
+/// Create an empty file with 0600 permissions; `openssl` keeps
+/// the mode when writing the key into an existing file.
+fn create_private(path: &Path) -> Result<()> {
+    fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+    fs::set_permissions(path, fs::Permissions::from_mode(0o600))?;
+    Ok(())
+}
+
+pub fn create_ca(name: &str) -> Result<()> {
+    fs::create_dir_all(folder())?;
+    create_private(&ca_key())?;
+    cmd!(
+        "openssl",
+        "req",
+        "-x509",
+        "-newkey",
+        "ec",
+        "-pkeyopt",
+        "ec_paramgen_curve:prime256v1",
+        "-nodes",
+        "-keyout",
+        ca_key(),
+        "-out",
+        ca_crt(),
+        "-days",
+        CA_VALIDITY_DAYS.to_string(),
+        "-subj",
+        format!("/CN={name}"),
+        "-addext",
+        "basicConstraints=critical,CA:TRUE",
+        "-addext",
+        "keyUsage=critical,keyCertSign,cRLSign"
+    )
+    .run()?;
+    Ok(())
+}
+
+/// Subject alternative name entry for a hostname or an IP address
+fn san(name: &str) -> String {
+    if name.parse::<IpAddr>().is_ok() {
+        format!("IP:{name}")
+    } else {
+        format!("DNS:{name}")
+    }
+}
+
+/// Issue a server certificate signed by the local CA
+pub fn issue(names: &[String]) -> Result<()> {
+    let Some(common_name) = names.first() else {
+        return Err(Error::custom("No hostnames specified for the certificate"));
+    };
+
+    let certs = certs();
+    let csr = folder().join("server.csr");
+    let ext = folder().join("server.ext");
+    let san = names.iter().map(|name| san(name)).collect::<Vec<_>>();
+    fs::write(
+        &ext,
+        format!(
+            "basicConstraints=CA:FALSE\nkeyUsage=critical,digitalSignature,keyEncipherment\nextendedKeyUsage=serverAuth\nsubjectAltName={}\n",
+            san.join(",")
+        ),
+    )?;
+
+    create_private(Path::new(&certs.key))?;
+    cmd!(
+        "openssl",
+        "req",
+        "-new",
+        "-newkey",
+        "ec",
+        "-pkeyopt",
+        "ec_paramgen_curve:prime256v1",
+        "-nodes",
+        "-keyout",
+        &certs.key,
+        "-out",
+        &csr,
+        "-subj",
+        format!("/CN={common_name}")
+    )
+    .run()?;
+
+    cmd!(
+        "openssl",
+        "x509",
+        "-req",
+        "-in",
+        &csr,
+        "-CA",
+        ca_crt(),
+        "-CAkey",
+        ca_key(),
+        "-CAcreateserial",
+        "-out",
+        &certs.crt,
+        "-days",
+        SERVER_VALIDITY_DAYS.to_string(),
+        "-extfile",
+        &ext
+    )
+    .run()?;
+
+    fs::remove_file(csr).ok();
+    fs::remove_file(ext).ok();
+
+    Ok(())
+}
+
+/// SHA-256 fingerprint of the CA certificate for client pinning
+pub fn ca_fingerprint() -> Result<String> {
+    let output = cmd!(
+        "openssl",
+        "x509",
+        "-noout",
+        "-fingerprint",
+        "-sha256",
+        "-in",
+        ca_crt()
+    )
+    .read()?;
+    Ok(output
+        .split_once('=')
+        .map(|(_, fingerprint)| fingerprint.trim().to_string())
+        .unwrap_or(output))
+}
+
+/// Subject alternative names are IP addresses or domain names
+fn validate_name(name: &str) -> Result<()> {
+    if IpAddr::from_str(name).is_ok() {
+        Ok(())
+    } else {
+        fqdn::validate(name)
+    }
+}
+
+/// Copy the CA certificate to a location chosen by the user
+pub fn export_ca() -> Result<()> {
+    let default = home_folder().join("khost-ca.crt");
+    let path: String = cliclack::input("Export CA certificate to:")
+        .default_input(&default.display().to_string())
+        .interact()?;
+    let path = PathBuf::from(path.replace('~', home_folder().to_str().unwrap()));
+    fs::copy(ca_crt(), &path)?;
+    log::success(format!(
+        "CA certificate exported to `{}`\nSHA-256 fingerprint: {}",
+        path.display(),
+        ca_fingerprint()?
+    ))?;
+    Ok(())
+}
+
+/// Create (or reuse) the local CA and issue a server certificate
+pub fn configure(ctx: &mut Context) -> Result<()> {
+    if ca_exists() {
+        log::info(format!(
+            "Using existing certificate authority at `{}`",
+            ca_crt().display()
+        ))?;
+    } else {
+        let name: String = cliclack::input("Certificate authority name:")
+            .default_input("kHOST Private CA")
+            .validate(|input: &String| {
+                if input.is_empty() || input.contains('/') {
+                    Err("Please enter a valid name".to_string())
+                } else {
+                    Ok(())
+                }
+            })
+            .interact()?;
+        step("Creating certificate authority...", || create_ca(&name))?;
+    }
+
+    let mut names = fqdn::configured(ctx)
+        .into_iter()
+        .filter(|fqdn| !fqdn.starts_with("*."))
+        .collect::<Vec<_>>();
+    if let Ok(hostname) = cmd!("hostname").read() {
+        let hostname = hostname.trim().to_string();
+        if !hostname.is_empty() && !names.contains(&hostname) {
+            names.push(hostname);
+        }
+    }
+
+    let input: String = cliclack::input("Hostnames and IP addresses for the certificate:")
+        .default_input(&names.join(" "))
+        .validate(|input: &String| {
+            if input.trim().is_empty() {
+                return Err("Please enter at least one hostname or IP address".to_string());
+            }
+            input
+                .split_whitespace()
+                .try_for_each(validate_name)
+                .map_err(|err| err.to_string())
+        })
+        .interact()?;
+    let names = input
+        .split_whitespace()
+        .map(|name| name.to_lowercase())
+        .collect::<Vec<_>>();
+
+    step(
+        format!("Issuing certificate for {}", names.join(", ")),
+        || issue(&names),
+    )?;
+    ctx.config.nginx.enable_certs(certs());
+    ctx.config.save()?;
+
+    if confirm("Export CA certificate for client pinning?")
+        .initial_value(true)
+        .interact()?
+    {
+        export_ca()?;
+    }
+
+    Ok(())
+}
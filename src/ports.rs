@@ -0,0 +1,288 @@
+use crate::imports::*;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// `TCP_LISTEN` socket state in `/proc/net/tcp{,6}`
+const TCP_LISTEN: &str = "0A";
+
+/// Listening TCP socket
+#[derive(Debug, Clone)]
+pub struct Listener {
+    pub addr: IpAddr,
+    pub port: u16,
+    pub inode: u64,
+}
+
+/// Process holding a listening socket
+#[derive(Debug, Clone)]
+pub struct Owner {
+    pub pid: u32,
+    pub name: String,
+    /// systemd unit from the process cgroup
+    pub unit: Option<String>,
+}
+
+impl Display for Owner {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` (pid {}", self.name, self.pid)?;
+        if let Some(unit) = &self.unit {
+            write!(f, ", {unit}")?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Decode a `/proc/net/tcp{,6}` address (network byte order words in host order)
+fn parse_addr(hex: &str) -> Option<IpAddr> {
+    let words = (0..hex.len() / 8)
+        .map(|n| u32::from_str_radix(hex.get(n * 8..n * 8 + 8)?, 16).ok())
+        .collect::<Option<Vec<_>>>()?;
+    let bytes = words
+        .iter()
+        .flat_map(|word| word.to_le_bytes())
+        .collect::<Vec<_>>();
+    match bytes.len() {
+        4 => Some(IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(bytes).ok()?))),
+        16 => Some(IpAddr::V6(Ipv6Addr::from(
+            <[u8; 16]>::try_from(bytes).ok()?,
+        ))),
+        _ => None,
+    }
+}
+
+fn parse_line(line: &str) -> Option<Listener> {
+    let fields = line.split_whitespace().collect::<Vec<_>>();
+    if fields.get(3) != Some(&TCP_LISTEN) {
+        return None;
+    }
+    let (addr, port) = fields.get(1)?.split_once(':')?;
+    Some(Listener {
+        addr: parse_addr(addr)?,
+        port: u16::from_str_radix(port, 16).ok()?,
+        inode: fields.get(9)?.parse().ok()?,
+    })
+}
+
+/// TCP listeners on all addresses (IPv4 and IPv6)
+pub fn listeners() -> Vec<Listener> {
+    ["/proc/net/tcp", "/proc/net/tcp6"]
+        .iter()
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .flat_map(|text| {
+            text.lines()
+                .skip(1)
+                .filter_map(parse_line)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn process(pid: u32) -> Owner {
+    let proc = Path::new("/proc").join(pid.to_string());
+    let name = fs::read_to_string(proc.join("comm"))
+        .map(|name| name.trim().to_string())
+        .unwrap_or_else(|_| "unknown".to_string());
+    // e.g. `0::/system.slice/nginx.service`
+    let unit = fs::read_to_string(proc.join("cgroup"))
+        .ok()
+        .and_then(|cgroup| {
+            cgroup
+                .lines()
+                .flat_map(|line| line.rsplit('/'))
+                .find(|name| name.ends_with(".service"))
+                .map(String::from)
+        });
+    Owner { pid, name, unit }
+}
+
+/// Owners of socket inodes from `/proc/<pid>/fd` (only processes of the current user are visible)
+fn owners(inodes: &HashSet<u64>) -> HashMap<u64, Owner> {
+    let mut owners = HashMap::new();
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return owners;
+    };
+    for entry in entries.flatten() {
+        let Some(pid) = entry.file_name().to_str().and_then(|pid| pid.parse().ok()) else {
+            continue;
+        };
+        let Ok(fds) = fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+        for fd in fds.flatten() {
+            let inode = fs::read_link(fd.path()).ok().and_then(|link| {
+                link.to_str()?
+                    .strip_prefix("socket:[")?
+                    .strip_suffix(']')?
+                    .parse::<u64>()
+                    .ok()
+            });
+            if let Some(inode) = inode.filter(|inode| inodes.contains(inode)) {
+                owners.entry(inode).or_insert_with(|| process(pid));
+            }
+        }
+    }
+    owners
+}
+
+/// Owning process ids by port from `ss` (with privileges, for processes of other users)
+fn owners_by_port() -> HashMap<u16, u32> {
+    sudo!("ss", "-ltnpH")
+        .unchecked()
+        .read()
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let port = fields.get(3)?.rsplit(':').next()?.parse().ok()?;
+            let (_, pid) = line.split_once("pid=")?;
+            let pid = pid
+                .split(|c: char| !c.is_ascii_digit())
+                .next()?
+                .parse()
+                .ok()?;
+            Some((port, pid))
+        })
+        .collect()
+}
+
+/// Port a service is configured to listen on
+#[derive(Debug, Clone)]
+pub struct Expected {
+    pub service: String,
+    pub label: String,
+    pub port: u16,
+}
+
+fn expected(ctx: &Context) -> Vec<Expected> {
+    let mut expected = Vec::new();
+    let mut push = |service: String, label: &str, port: u16| {
+        expected.push(Expected {
+            service,
+            label: label.to_string(),
+            port,
+        })
+    };
+
+    for config in kaspad::active_configs(ctx) {
+        for (label, port) in config.ports() {
+            push(config.service_name(), label, port);
+        }
+    }
+
+    let resolver = &ctx.config.resolver;
+    if resolver.enabled() {
+        if let Some(http) = &resolver.http {
+            push(resolver.service_name(), "http", http.port());
+        }
+    }
+
+    let exporter = &ctx.config.exporter;
+    if exporter.enabled() {
+        push(exporter.service_name(), "http", exporter.port);
+    }
+
+    let nginx = &ctx.config.nginx;
+    push(nginx.service_name(), "http", 80);
+    if nginx.certs().is_some() {
+        push(nginx.service_name(), "https", 443);
+    }
+
+    expected
+}
+
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub expected: Expected,
+    pub addr: IpAddr,
+    pub owner: Option<Owner>,
+}
+
+impl Display for Conflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Port {} ({} {}) is held by ",
+            self.expected.port, self.expected.service, self.expected.label
+        )?;
+        match &self.owner {
+            Some(owner) => write!(f, "{owner}")?,
+            None => write!(f, "another process")?,
+        }
+        write!(f, " on {}", self.addr)
+    }
+}
+
+/// Configured ports held by processes other than the service they are assigned to
+pub fn conflicts(ctx: &Context) -> Vec<Conflict> {
+    let expected = expected(ctx);
+    let ports = expected
+        .iter()
+        .map(|expected| expected.port)
+        .collect::<HashSet<_>>();
+    let listeners = listeners()
+        .into_iter()
+        .filter(|listener| ports.contains(&listener.port))
+        .collect::<Vec<_>>();
+    if listeners.is_empty() {
+        return vec![];
+    }
+
+    let inodes = listeners
+        .iter()
+        .map(|listener| listener.inode)
+        .collect::<HashSet<_>>();
+    let mut owners = owners(&inodes);
+    if listeners
+        .iter()
+        .any(|listener| !owners.contains_key(&listener.inode))
+    {
+        let by_port = owners_by_port();
+        for listener in listeners.iter() {
+            if let Some(pid) = by_port.get(&listener.port) {
+                owners
+                    .entry(listener.inode)
+                    .or_insert_with(|| process(*pid));
+            }
+        }
+    }
+
+    let mut conflicts = Vec::new();
+    for expected in expected {
+        let unit = format!("{}.service", expected.service);
+        // IPv4 and IPv6 sockets of the same process report the port once
+        let mut seen = HashSet::new();
+        for listener in listeners.iter().filter(|l| l.port == expected.port) {
+            let owner = owners.get(&listener.inode).cloned();
+            let is_expected = match &owner {
+                Some(owner) => owner.unit.as_ref() == Some(&unit),
+                // not visible to this user; trust the unit if it is running
+                None => systemd::show(&expected.service, &["ActiveState"])
+                    .get("ActiveState")
+                    .is_some_and(|state| state == "active"),
+            };
+            if !is_expected && seen.insert(owner.as_ref().map(|owner| owner.pid)) {
+                conflicts.push(Conflict {
+                    expected: expected.clone(),
+                    addr: listener.addr,
+                    owner,
+                });
+            }
+        }
+    }
+    conflicts
+}
+
+/// Report conflicts before installing services; returns `false` if the user aborts
+pub fn confirm_install(ctx: &Context) -> Result<bool> {
+    let conflicts = conflicts(ctx);
+    if conflicts.is_empty() {
+        return Ok(true);
+    }
+    for conflict in conflicts.iter() {
+        log::error(conflict)?;
+    }
+    Ok(
+        confirm("Ports required by kHOST services are in use. Continue anyway?")
+            .initial_value(false)
+            .interact()?,
+    )
+}
@@ -186,10 +186,11 @@ pub fn update(ctx: &Context) -> Result<()> {
         return Ok(());
     }
 
-    fetch(&config.origin)?;
-    build(&config.origin)?;
-    restart(ctx)?;
-    Ok(())
+    alerts::on_error(ctx, alerts::Kind::UpdateFailed, "resolver", || {
+        fetch(&config.origin)?;
+        build(&config.origin)?;
+        restart(ctx)
+    })
 }
 
 pub fn uninstall(ctx: &mut Context) -> Result<()> {
@@ -249,7 +250,7 @@ pub fn version(origin: &Origin) -> Option<String> {
         .and_then(|s| {
             s.trim()
                 .split(' ')
-                .last()
+                .next_back()
                 .map(|version| format!("{version}-{hash}"))
         })
 }
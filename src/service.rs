@@ -5,6 +5,8 @@ pub enum ServiceKind {
     Kaspad(Network),
     Resolver,
     Nginx,
+    Exporter,
+    Watchdog,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
@@ -89,8 +91,15 @@ pub fn enable_services(ctx: &mut Context, services: Vec<ServiceDetail>) -> Resul
         ctx.config.save()?;
     }
 
+    ctx.config.exporter.enabled = kinds.contains(&ServiceKind::Exporter);
+    ctx.config.watchdog.enabled = kinds.contains(&ServiceKind::Watchdog);
+    ctx.config.save()?;
+
     resolver::reconfigure(ctx, false)?;
+    exporter::reconfigure(ctx)?;
+    watchdog::reconfigure(ctx)?;
     kaspad::configure_networks(ctx, networks)?;
+    alerts::reconfigure(ctx)?;
     nginx::reconfigure(ctx)?;
 
     Ok(())
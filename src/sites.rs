@@ -0,0 +1,273 @@
+use crate::imports::*;
+
+const NGINX_SITE_FOLDERS: &[&str] = &["/etc/nginx/sites-enabled", "/etc/nginx/conf.d"];
+
+#[derive(Debug, Clone)]
+pub struct Listen {
+    pub port: u16,
+    pub default_server: bool,
+}
+
+impl Listen {
+    fn parse(args: &[&str]) -> Option<Self> {
+        let address = args.first()?;
+        if address.starts_with("unix:") {
+            return None;
+        }
+        let port = address
+            .rsplit_once(':')
+            .map(|(_, port)| port)
+            .unwrap_or(address);
+        // `listen 127.0.0.1;` defaults to port 80
+        let port = port.parse::<u16>().unwrap_or(80);
+        let default_server = args[1..]
+            .iter()
+            .any(|arg| *arg == "default_server" || *arg == "default");
+        Some(Self {
+            port,
+            default_server,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Server {
+    pub listen: Vec<Listen>,
+    pub server_names: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Site {
+    pub path: PathBuf,
+    pub servers: Vec<Server>,
+}
+
+impl Site {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = fs::read_to_string(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            servers: parse(&text),
+        })
+    }
+}
+
+/// Extract `server` blocks with their `listen` and `server_name` directives
+pub fn parse(text: &str) -> Vec<Server> {
+    let text = text
+        .lines()
+        .map(|line| line.split_once('#').map(|(l, _)| l).unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut servers = Vec::new();
+    let mut stack: Vec<Option<Server>> = Vec::new();
+    let mut statement = String::new();
+
+    for c in text.chars() {
+        match c {
+            '{' => {
+                let block = statement.split_whitespace().next().unwrap_or_default();
+                stack.push((block == "server").then(Server::default));
+                statement.clear();
+            }
+            '}' => {
+                if let Some(Some(mut server)) = stack.pop() {
+                    if server.listen.is_empty() {
+                        server.listen.push(Listen {
+                            port: 80,
+                            default_server: false,
+                        });
+                    }
+                    servers.push(server);
+                }
+                statement.clear();
+            }
+            ';' => {
+                let args = statement.split_whitespace().collect::<Vec<_>>();
+                if let Some(Some(server)) = stack.last_mut() {
+                    match args.first() {
+                        Some(&"listen") => {
+                            if let Some(listen) = Listen::parse(&args[1..]) {
+                                server.listen.push(listen);
+                            }
+                        }
+                        Some(&"server_name") => {
+                            server
+                                .server_names
+                                .extend(args[1..].iter().map(|name| name.to_lowercase()));
+                        }
+                        _ => {}
+                    }
+                }
+                statement.clear();
+            }
+            _ => statement.push(c),
+        }
+    }
+
+    servers
+}
+
+/// Check if two NGINX server names can match the same host
+fn overlaps(a: &str, b: &str) -> bool {
+    fn matches(wildcard: &str, name: &str) -> bool {
+        match wildcard.strip_prefix("*.") {
+            Some(suffix) => name
+                .strip_prefix("*.")
+                .unwrap_or(name)
+                .ends_with(&format!(".{suffix}")),
+            None => false,
+        }
+    }
+
+    a == b || matches(a, b) || matches(b, a)
+}
+
+pub struct SiteConflict {
+    pub path: PathBuf,
+    pub reasons: Vec<String>,
+}
+
+impl Display for SiteConflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} `{}`",
+            style("Conflicting NGINX site:").red().bright(),
+            self.path.display()
+        )?;
+        for reason in self.reasons.iter() {
+            write!(f, "\n  - {reason}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Ports NGINX listens on for the kHOST server block(s)
+pub fn ports(ctx: &Context) -> Vec<u16> {
+    if ctx.config.nginx.certs().is_some() {
+        vec![80, 443]
+    } else {
+        vec![80]
+    }
+}
+
+pub fn sites() -> Vec<Site> {
+    let own = nginx::config_filenames();
+    NGINX_SITE_FOLDERS
+        .iter()
+        .filter_map(|folder| fs::read_dir(folder).ok())
+        .flat_map(|entries| entries.flatten().map(|entry| entry.path()))
+        .filter(|path| !own.contains(path))
+        .filter(|path| {
+            // conf.d only includes `*.conf` files
+            !path.starts_with("/etc/nginx/conf.d")
+                || path.extension().is_some_and(|ext| ext == "conf")
+        })
+        .filter_map(|path| Site::load(&path).ok())
+        .collect()
+}
+
+pub fn conflicts(ctx: &Context) -> Vec<SiteConflict> {
+    let ports = ports(ctx);
+    let fqdns = fqdn::get(ctx);
+
+    sites()
+        .into_iter()
+        .filter_map(|site| {
+            let mut reasons = Vec::new();
+            for server in site.servers.iter() {
+                for listen in server.listen.iter().filter(|l| ports.contains(&l.port)) {
+                    if listen.default_server {
+                        reasons.push(format!("claims port {} as default_server", listen.port));
+                    }
+                    for name in server.server_names.iter() {
+                        if fqdns.iter().any(|fqdn| overlaps(fqdn, name)) {
+                            reasons.push(format!(
+                                "claims server name `{name}` on port {}",
+                                listen.port
+                            ));
+                        }
+                    }
+                }
+            }
+            reasons.dedup();
+            (!reasons.is_empty()).then_some(SiteConflict {
+                path: site.path,
+                reasons,
+            })
+        })
+        .collect()
+}
+
+pub fn backup_folder() -> PathBuf {
+    data_folder().join("nginx").join("backup")
+}
+
+/// Disable a site by removing it from the NGINX include folders,
+/// keeping a copy in the kHOST data folder.
+pub fn disable(path: &Path) -> Result<PathBuf> {
+    let folder = backup_folder();
+    fs::create_dir_all(&folder)?;
+    let ts = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let backup = folder.join(format!("{ts}-{name}"));
+    fs::copy(path, &backup)?;
+    sudo::fs::remove_file(path)?;
+    Ok(backup)
+}
+
+/// Re-enable a site disabled by [`disable`]; symlinked sites are linked again
+fn restore(path: &Path, backup: &Path, link: Option<&Path>) -> Result<()> {
+    match link {
+        Some(target) => sudo!("ln", "-sfn", target, path).run(),
+        None => sudo::fs::write(path, fs::read(backup)?),
+    }
+}
+
+/// Report conflicting sites and offer to disable them
+pub fn resolve(ctx: &Context) -> Result<()> {
+    let conflicts = conflicts(ctx);
+    if conflicts.is_empty() {
+        return Ok(());
+    }
+
+    let mut disabled = Vec::new();
+    for conflict in conflicts {
+        log::warning(&conflict)?;
+        if confirm(format!(
+            "Disable `{}`? (a backup will be kept in `{}`)",
+            conflict.path.display(),
+            backup_folder().display()
+        ))
+        .initial_value(true)
+        .interact()?
+        {
+            let link = fs::read_link(&conflict.path).ok();
+            let backup = disable(&conflict.path)?;
+            log::success(format!(
+                "Disabled `{}`, backup saved to `{}`",
+                conflict.path.display(),
+                backup.display()
+            ))?;
+            disabled.push((conflict.path.clone(), backup, link));
+        }
+    }
+
+    if !disabled.is_empty() {
+        if let Err(err) = nginx::test() {
+            for (path, backup, link) in disabled.iter() {
+                restore(path, backup, link.as_deref())?;
+            }
+            log::warning("Disabled sites have been restored")?;
+            return Err(err);
+        }
+        nginx::reload()?;
+    }
+
+    Ok(())
+}
@@ -7,6 +7,11 @@ pub struct Status {
     pub ip: Option<String>,
     pub system: Arc<System>,
     pub services: ServiceStateVec,
+    pub certs: Option<std::result::Result<tls::CertsStatus, String>>,
+    /// Live node state keyed by service name
+    pub health: HashMap<String, std::result::Result<health::Health, String>>,
+    /// Data folder growth and disk exhaustion forecast
+    pub storage: Vec<storage::Forecast>,
 }
 
 impl Display for Status {
@@ -38,6 +43,52 @@ impl Display for Status {
             if let Some(origin) = &service.origin {
                 rows.push(Content::field("", origin));
             }
+            match self.health.get(&service.name) {
+                Some(Ok(health)) if health.is_synced => {
+                    rows.push(Content::field("", style(health).green()));
+                }
+                Some(Ok(health)) => {
+                    rows.push(Content::field("", style(health).yellow()));
+                }
+                Some(Err(err)) => {
+                    rows.push(Content::field("", style(format!("wRPC: {err}")).red()));
+                }
+                None => {}
+            }
+        }
+
+        for storage in self.storage.iter() {
+            rows.push(Content::separator());
+            rows.extend(Vec::<Content>::from(storage));
+        }
+
+        if let Some(certs) = &self.certs {
+            rows.push(Content::separator());
+            match certs {
+                Ok(certs) => {
+                    let leaf = certs.leaf();
+                    rows.push(Content::field(
+                        "TLS subject:",
+                        style(&leaf.subject).cyan().bright(),
+                    ));
+                    rows.push(Content::field("TLS names:", leaf.san.join(" ")));
+                    rows.push(Content::field("TLS issuer:", &leaf.issuer));
+                    let expiry = format!(
+                        "{} ({} days)",
+                        leaf.not_after.format("%Y-%m-%d"),
+                        leaf.days_remaining()
+                    );
+                    let expiry = if leaf.days_remaining() < tls::EXPIRY_WARNING_DAYS {
+                        style(expiry).red().bright()
+                    } else {
+                        style(expiry).green().bright()
+                    };
+                    rows.push(Content::field("TLS expires:", expiry));
+                }
+                Err(err) => {
+                    rows.push(Content::field("TLS:", style(err).red().bright()));
+                }
+            }
         }
 
         writeln!(f, "{}", content(rows))?;
@@ -69,10 +120,21 @@ pub fn detect(ctx: &Context) -> Status {
         })
         .collect();
 
+    let certs = ctx
+        .config
+        .nginx
+        .certs()
+        .map(|certs| tls::CertsStatus::try_new(&certs).map_err(|err| err.to_string()));
+
+    let health = health::detect(ctx);
+
     Status {
         ip,
         system,
         services,
+        certs,
+        health,
+        storage: storage::forecast(),
         // errors,
     }
 }
@@ -100,7 +162,61 @@ impl Conflict {
     }
 }
 
-pub fn conflicts(ctx: &Context, _status: &Status) {
+impl Display for Conflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Conflict::Warning(msg) => write!(f, "warning: {msg}"),
+            Conflict::Error(msg) => write!(f, "error: {msg}"),
+        }
+    }
+}
+
+/// Configuration and host issues (ports, NGINX sites, certificates, bandwidth, storage, hardware)
+pub fn report(ctx: &Context, status: &Status) -> Vec<Conflict> {
+    let mut conflicts = ports::conflicts(ctx)
+        .into_iter()
+        .map(Conflict::error)
+        .collect::<Vec<_>>();
+
+    // resolved from Configure > Resolve NGINX site conflicts
+    conflicts.extend(sites::conflicts(ctx).into_iter().map(|site| {
+        Conflict::warning(format!(
+            "Conflicting NGINX site `{}`: {}",
+            site.path.display(),
+            site.reasons.join("; ")
+        ))
+    }));
+
+    match &status.certs {
+        Some(Ok(certs)) => {
+            conflicts.extend(
+                certs
+                    .issues(&fqdn::get(ctx))
+                    .into_iter()
+                    .map(Conflict::warning),
+            );
+        }
+        Some(Err(err)) => {
+            conflicts.push(Conflict::error(format!(
+                "Unable to inspect SSL certificates: {err}"
+            )));
+        }
+        None => {}
+    }
+
+    conflicts.extend(bandwidth::conflicts(ctx).into_iter().map(Conflict::warning));
+
+    conflicts.extend(
+        storage::conflicts(&status.storage)
+            .into_iter()
+            .map(Conflict::warning),
+    );
+
+    conflicts.extend(hardware::conflicts(ctx));
+    conflicts
+}
+
+pub fn conflicts(ctx: &Context, status: &Status) {
     use sysinfo::*;
 
     let mut system = System::new();
@@ -142,22 +258,8 @@ pub fn conflicts(ctx: &Context, _status: &Status) {
         std::process::exit(1);
     }
 
-    let networks = ctx
-        .config
-        .kaspad
-        .iter()
-        .map(Service::enabled)
-        .filter(|enabled| *enabled)
-        .collect::<Vec<_>>()
-        .len();
-
-    if !kaspad::supports_multiple_networks(ctx, networks) {
-        log::error(format!(
-            "Detected RAM of {} is insufficient for {} networks!",
-            as_gb(ctx.system.total_memory as f64, false, false),
-            networks
-        ))
-        .ok();
+    for conflict in report(ctx, status) {
+        conflict.render().ok();
     }
 
     rust::check().ok();
@@ -0,0 +1,327 @@
+use crate::imports::*;
+use chrono::{DateTime, Duration as ChronoDuration, Local};
+use std::collections::BTreeMap;
+use std::io::Write;
+
+/// Hourly sampling unit (`khost storage`)
+const UNIT_NAME: &str = "khost-storage";
+
+/// Samples older than this are discarded
+const HISTORY_DAYS: i64 = 30;
+/// Samples used for growth rates
+const FORECAST_DAYS: i64 = 7;
+/// Minimum span of samples required for a forecast
+const FORECAST_MIN_HOURS: i64 = 6;
+/// Warn when the disk is forecast to fill up within this many days
+pub const WARNING_DAYS: f64 = 30.0;
+
+const SECS_PER_DAY: f64 = 86400.0;
+
+pub fn folder_size(path: &Path) -> Option<u64> {
+    cmd!("du", "-sb", path)
+        .unchecked()
+        .read()
+        .ok()?
+        .split_whitespace()
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Service data folders keyed by service name
+pub fn data_folders(ctx: &Context) -> Vec<(String, PathBuf)> {
+    kaspad::active_configs(ctx)
+        .map(|config| (config.service_name(), config.data_folder()))
+        .chain(ctx.config.resolver.enabled().then(|| {
+            (
+                resolver::SERVICE_NAME.to_string(),
+                resolver::resolver_config_folder(),
+            )
+        }))
+        .chain([("khost".to_string(), data_folder())])
+        .collect()
+}
+
+/// Filesystem holding one or more data folders
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Usage {
+    pub total: u64,
+    pub available: u64,
+    /// Data folder sizes keyed by service name
+    pub folders: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sample {
+    pub time: DateTime<Local>,
+    /// Filesystems keyed by mount point
+    pub filesystems: BTreeMap<String, Usage>,
+}
+
+pub fn history_filename() -> PathBuf {
+    data_folder().join("storage-history.jsonl")
+}
+
+pub fn history() -> Vec<Sample> {
+    fs::read_to_string(history_filename())
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+/// Take a sample and append it to the history (`khost storage`)
+pub fn record(ctx: &Context) -> Result<()> {
+    let mut filesystems = BTreeMap::new();
+    for (service, path) in data_folders(ctx) {
+        let Some(filesystem) = system::filesystem(&path) else {
+            continue;
+        };
+        let usage = filesystems.entry(filesystem.mount).or_insert(Usage {
+            total: filesystem.total,
+            available: filesystem.available,
+            folders: BTreeMap::new(),
+        });
+        if let Some(size) = folder_size(&path) {
+            usage.folders.insert(service, size);
+        }
+    }
+    if filesystems.is_empty() {
+        return Err(Error::custom("Unable to determine disk usage"));
+    }
+    let sample = Sample {
+        time: Local::now(),
+        filesystems,
+    };
+
+    let since = sample.time - ChronoDuration::days(HISTORY_DAYS);
+    let history = history();
+    if history.first().is_some_and(|first| first.time < since) {
+        let text = history
+            .iter()
+            .filter(|sample| sample.time >= since)
+            .chain([&sample])
+            .map(serde_json::to_string)
+            .collect::<std::result::Result<Vec<_>, _>>()?
+            .join("\n");
+        fs::write(history_filename(), text + "\n")?;
+    } else {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(history_filename())?;
+        writeln!(file, "{}", serde_json::to_string(&sample)?)?;
+    }
+    Ok(())
+}
+
+/// Least-squares slope of `value` over time, in units per day
+fn slope(points: &[(f64, f64)]) -> f64 {
+    let n = points.len() as f64;
+    let (mean_t, mean_v) = points
+        .iter()
+        .fold((0.0, 0.0), |(t, v), (pt, pv)| (t + pt / n, v + pv / n));
+    let (cov, var) = points.iter().fold((0.0, 0.0), |(cov, var), (t, v)| {
+        (
+            cov + (t - mean_t) * (v - mean_v),
+            var + (t - mean_t) * (t - mean_t),
+        )
+    });
+    if var > 0.0 {
+        cov / var * SECS_PER_DAY
+    } else {
+        0.0
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Growth {
+    pub service: String,
+    pub size: u64,
+    /// Bytes per day
+    pub rate: f64,
+}
+
+/// Growth forecast of one filesystem
+#[derive(Debug, Clone)]
+pub struct Forecast {
+    pub mount: String,
+    pub total: u64,
+    pub available: u64,
+    pub folders: Vec<Growth>,
+    /// Decrease of available space in bytes per day
+    pub rate: f64,
+}
+
+impl Forecast {
+    /// Estimate for the filesystem mounted at `mount` from recorded samples;
+    /// `None` until enough history is available
+    pub fn new(history: &[Sample], mount: &str) -> Option<Self> {
+        let samples = history
+            .iter()
+            .filter_map(|sample| {
+                sample
+                    .filesystems
+                    .get(mount)
+                    .map(|usage| (sample.time, usage))
+            })
+            .collect::<Vec<_>>();
+        let (last_time, last) = *samples.last()?;
+        let since = last_time - ChronoDuration::days(FORECAST_DAYS);
+        let samples = samples
+            .into_iter()
+            .filter(|(time, _)| *time >= since)
+            .collect::<Vec<_>>();
+        if last_time - samples.first()?.0 < ChronoDuration::hours(FORECAST_MIN_HOURS) {
+            return None;
+        }
+
+        let points = |value: &dyn Fn(&Usage) -> Option<u64>| {
+            samples
+                .iter()
+                .filter_map(|(time, usage)| {
+                    value(usage).map(|value| (time.timestamp() as f64, value as f64))
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let folders = last
+            .folders
+            .iter()
+            .map(|(service, size)| Growth {
+                service: service.clone(),
+                size: *size,
+                rate: slope(&points(&|usage| usage.folders.get(service).copied())),
+            })
+            .collect();
+
+        Some(Self {
+            mount: mount.to_string(),
+            total: last.total,
+            available: last.available,
+            folders,
+            rate: -slope(&points(&|usage| Some(usage.available))),
+        })
+    }
+
+    pub fn days_until_full(&self) -> Option<f64> {
+        (self.rate > 0.0).then(|| self.available as f64 / self.rate)
+    }
+
+    pub fn is_critical(&self) -> bool {
+        self.days_until_full()
+            .is_some_and(|days| days < WARNING_DAYS)
+    }
+}
+
+fn rate(bytes_per_day: f64) -> String {
+    let sign = if bytes_per_day < 0.0 { "-" } else { "+" };
+    format!("{sign}{}/day", as_data_size(bytes_per_day.abs(), false))
+}
+
+impl From<&Forecast> for Vec<Content> {
+    fn from(forecast: &Forecast) -> Self {
+        let mut rows = Vec::new();
+        for growth in forecast.folders.iter() {
+            rows.push(Content::field(
+                format!("{}:", growth.service),
+                format!(
+                    "{} ({})",
+                    as_data_size(growth.size as f64, false),
+                    rate(growth.rate)
+                ),
+            ));
+        }
+        let full = match forecast.days_until_full() {
+            Some(days) if forecast.is_critical() => {
+                style(format!("~{days:1.0} days ({})", rate(-forecast.rate)))
+                    .red()
+                    .bright()
+            }
+            Some(days) => style(format!("~{days:1.0} days ({})", rate(-forecast.rate)))
+                .green()
+                .bright(),
+            None => style("not growing".to_string()).green().bright(),
+        };
+        rows.push(Content::field(format!("{} full in:", forecast.mount), full));
+        rows
+    }
+}
+
+/// Forecasts for each filesystem of the most recent sample
+pub fn forecast() -> Vec<Forecast> {
+    let history = history();
+    let Some(last) = history.last() else {
+        return vec![];
+    };
+    last.filesystems
+        .keys()
+        .filter_map(|mount| Forecast::new(&history, mount))
+        .collect()
+}
+
+pub fn conflicts(forecasts: &[Forecast]) -> Vec<String> {
+    forecasts
+        .iter()
+        .filter_map(|forecast| match forecast.days_until_full() {
+            Some(days) if forecast.is_critical() => Some(format!(
+                "Disk space on `{}` is forecast to run out in ~{days:1.0} days ({} available, {})",
+                forecast.mount,
+                as_data_size(forecast.available as f64, false),
+                rate(-forecast.rate)
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+struct StorageUnit {
+    user: String,
+    binary: String,
+}
+
+impl Display for StorageUnit {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[Unit]")?;
+        writeln!(f, "Description=kHOST storage usage sampling")?;
+        writeln!(f)?;
+        writeln!(f, "[Service]")?;
+        writeln!(f, "Type=oneshot")?;
+        writeln!(f, "User={}", self.user)?;
+        writeln!(f, "Nice=10")?;
+        writeln!(f, "IOSchedulingClass=idle")?;
+        writeln!(f, "ExecStart={} storage", self.binary)?;
+        Ok(())
+    }
+}
+
+struct StorageTimer;
+
+impl Display for StorageTimer {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "[Unit]")?;
+        writeln!(f, "Description=kHOST storage usage sampling timer")?;
+        writeln!(f)?;
+        writeln!(f, "[Timer]")?;
+        writeln!(f, "OnBootSec=10min")?;
+        writeln!(f, "OnUnitActiveSec=1h")?;
+        writeln!(f)?;
+        writeln!(f, "[Install]")?;
+        writeln!(f, "WantedBy=timers.target")?;
+        Ok(())
+    }
+}
+
+/// Install (or refresh) the sampling timer
+pub fn reconfigure(ctx: &Context) -> Result<()> {
+    step("Configuring storage sampling", || {
+        let unit = StorageUnit {
+            user: ctx.username.clone(),
+            binary: khost::binary()?.display().to_string(),
+        };
+        sudo::fs::write(systemd::service_path(UNIT_NAME), unit.to_string())?;
+        sudo::fs::write(systemd::timer_path(UNIT_NAME), StorageTimer.to_string())?;
+        systemd::daemon_reload()?;
+        sudo!("systemctl", "enable", "--now", format!("{UNIT_NAME}.timer")).run()
+    })
+}
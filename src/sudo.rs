@@ -45,10 +45,30 @@ pub mod fs {
         P: AsRef<Path>,
         C: AsRef<[u8]>,
     {
+        write_with_permissions(path, content, "root:root", "644")
+    }
+
+    pub fn write_with_permissions<P, C, O, M>(path: P, content: C, owner: O, mode: M) -> Result<()>
+    where
+        P: AsRef<Path>,
+        C: AsRef<[u8]>,
+        O: Into<OsString>,
+        M: Into<OsString>,
+    {
+        use std::io::Write;
+        use std::os::unix::fs::OpenOptionsExt;
+
+        // created private (content may be sensitive); `mode` is applied before the move
         let temp = temp_folder().join("temp.txt");
-        fs::write(&temp, content)?;
-        sudo!("chown", "root:root", &temp).run()?;
-        sudo!("chmod", "644", &temp).run()?;
+        fs::remove_file(&temp).ok();
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&temp)?
+            .write_all(content.as_ref())?;
+        sudo!("chown", owner, &temp).run()?;
+        sudo!("chmod", mode, &temp).run()?;
         sudo!("mv", "-f", temp, path.as_ref()).run()?;
         Ok(())
     }
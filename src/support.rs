@@ -0,0 +1,197 @@
+use crate::imports::*;
+use chrono::Local;
+
+/// Journal lines included per service
+const JOURNAL_LINES: &str = "1000";
+
+/// Config keys whose values (including whole objects such as `auth` and `smtp`) are replaced in the bundle
+const REDACTED_KEYS: &[&str] = &[
+    "email", "webhook", "smtp", "auth", "hash", "from", "to", "password", "secret", "token",
+];
+const REDACTED: &str = "<redacted>";
+
+/// Unit files and NGINX configuration of the active services
+pub fn config_files(ctx: &Context) -> Vec<PathBuf> {
+    ctx.managed_active_services()
+        .into_iter()
+        .map(|detail| systemd::service_path(detail.name.as_str()))
+        .chain(nginx::config_filenames())
+        .collect()
+}
+
+fn redact(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if REDACTED_KEYS.contains(&key.as_str()) && !value.is_null() {
+                    *value = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+/// `config.json` with contacts and credentials removed
+pub fn redacted_config(config: &Config) -> Result<String> {
+    let mut config = serde_json::to_value(config)?;
+    redact(&mut config);
+    Ok(serde_json::to_string_pretty(&config)?)
+}
+
+fn versions(ctx: &Context) -> String {
+    let mut versions = vec![("khost".to_string(), Some(khost::version().to_string()))];
+    for config in ctx.config.kaspad.iter() {
+        versions.push((
+            format!("kaspad ({})", config.service_name()),
+            config.origin().and_then(|origin| kaspad::version(&origin)),
+        ));
+    }
+    versions.push((
+        "resolver".to_string(),
+        resolver::version(&ctx.config.resolver.origin),
+    ));
+    versions.push(("nginx".to_string(), nginx::version()));
+    versions.push(("git".to_string(), git::version()));
+    versions.push(("protoc".to_string(), base::protoc::version()));
+
+    versions
+        .into_iter()
+        .map(|(name, version)| {
+            format!(
+                "{name}: {}\n",
+                version.unwrap_or_else(|| "not installed".to_string())
+            )
+        })
+        .collect()
+}
+
+fn journal(service: &str) -> String {
+    sudo!(
+        "journalctl",
+        "-u",
+        service,
+        "-n",
+        JOURNAL_LINES,
+        "-o",
+        "short-iso",
+        "--no-pager"
+    )
+    .unchecked()
+    .read()
+    .unwrap_or_else(|err| format!("Unable to read journal: {err}"))
+}
+
+/// Collect diagnostics into `~/khost-support-<timestamp>.tar.gz`
+pub fn create_bundle(ctx: &Context) -> Result<PathBuf> {
+    let name = format!("khost-support-{}", Local::now().format("%Y%m%d-%H%M%S"));
+    let folder = temp_folder().join(&name);
+    let archive = home_folder().join(format!("{name}.tar.gz"));
+    fs::create_dir_all(folder.join("files"))?;
+    fs::create_dir_all(folder.join("journal"))?;
+
+    fs::write(folder.join("config.json"), redacted_config(&ctx.config)?)?;
+
+    for path in config_files(ctx) {
+        let text = fs::read_to_string(&path)
+            .unwrap_or_else(|err| format!("Unable to read {}: {err}\n", path.display()));
+        let filename = path
+            .to_string_lossy()
+            .trim_start_matches('/')
+            .replace('/', "_");
+        fs::write(folder.join("files").join(filename), text)?;
+    }
+
+    for detail in ctx.active_services() {
+        fs::write(
+            folder.join("journal").join(format!("{}.log", detail.name)),
+            journal(&detail.name),
+        )?;
+    }
+
+    let status = status::detect(ctx);
+    fs::write(
+        folder.join("status.txt"),
+        ::console::strip_ansi_codes(&status.to_string()).as_ref(),
+    )?;
+    fs::write(folder.join("versions.txt"), versions(ctx))?;
+    let conflicts = status::report(ctx, &status)
+        .into_iter()
+        .map(|conflict| format!("{conflict}\n"))
+        .collect::<String>();
+    fs::write(folder.join("conflicts.txt"), conflicts)?;
+
+    let result = cmd!("tar", "czf", &archive, "-C", temp_folder(), &name).run();
+    fs::remove_dir_all(&folder).ok();
+    result?;
+    Ok(archive)
+}
+
+pub fn bundle(ctx: &Context) -> Result<()> {
+    log::info(
+        "The support bundle includes service configuration, recent logs and system status.\nContacts and credentials are removed from config.json.",
+    )?;
+    let mut archive = None;
+    step("Creating support bundle...", || {
+        archive = Some(create_bundle(ctx)?);
+        Ok(())
+    })?;
+    if let Some(archive) = archive {
+        log::success(format!(
+            "Support bundle created: {}",
+            style(archive.display()).cyan()
+        ))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use access::{Access, BasicAuth, Credential};
+
+    #[test]
+    fn removes_contacts_and_credentials() {
+        let mut config: Config =
+            serde_json::from_str(include_str!("../tests/fixtures/config-v2.json")).unwrap();
+        config.acme.email = Some("admin@example.com".to_string());
+        config.alerts.webhook = Some("https://hooks.example.com/T0KEN".to_string());
+        config.alerts.smtp = Some(alerts::Smtp {
+            host: "smtp.example.com".to_string(),
+            port: 25,
+            from: "khost@example.com".to_string(),
+            to: vec!["ops@example.com".to_string()],
+        });
+        config.nginx.set_access(
+            "/mainnet",
+            Access {
+                allow: vec![],
+                deny: vec![],
+                auth: Some(BasicAuth {
+                    realm: "kHOST".to_string(),
+                    users: vec![Credential {
+                        user: "operator".to_string(),
+                        hash: "$apr1$salt$digest".to_string(),
+                    }],
+                }),
+            },
+        );
+
+        let redacted = redacted_config(&config).unwrap();
+        for secret in [
+            "admin@example.com",
+            "hooks.example.com",
+            "smtp.example.com",
+            "khost@example.com",
+            "ops@example.com",
+            "operator",
+            "$apr1$salt$digest",
+        ] {
+            assert!(!redacted.contains(secret), "`{secret}` in {redacted}");
+        }
+        assert!(redacted.contains(REDACTED));
+    }
+}
@@ -152,6 +152,29 @@ impl DiskUsage {
         self.available as f64 / self.total as f64
     }
 }
+/// Filesystem holding a folder
+#[derive(Debug, Clone)]
+pub struct Filesystem {
+    pub mount: String,
+    pub fstype: String,
+    pub total: u64,
+    pub available: u64,
+}
+
+/// Filesystem holding `folder` (or its closest existing parent) via `df`
+pub fn filesystem(folder: &Path) -> Option<Filesystem> {
+    let folder = folder.ancestors().find(|path| path.exists())?;
+    let output = cmd!("df", "-PkT", folder).read().ok()?;
+    let line = output.lines().last()?;
+    let parts = line.split_whitespace().collect::<Vec<_>>();
+    Some(Filesystem {
+        mount: parts.get(6..).filter(|mount| !mount.is_empty())?.join(" "),
+        fstype: parts.get(1)?.to_string(),
+        total: parts.get(2)?.parse::<u64>().ok()? * 1024,
+        available: parts.get(4)?.parse::<u64>().ok()? * 1024,
+    })
+}
+
 pub fn disk_usage() -> Option<DiskUsage> {
     let lines = cmd!("df", "-k", home_folder()).read().ok()?;
     let lines = lines.lines();
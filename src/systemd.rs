@@ -8,6 +8,10 @@ pub struct Config {
     pub user: String,
     pub exec_start: String,
     pub restart_secs: u64,
+    /// `Environment=` assignments (`KEY=value`)
+    pub environment: Vec<String>,
+    /// Optional `EnvironmentFile=` overriding `environment`
+    pub environment_file: Option<PathBuf>,
 }
 
 impl Display for Config {
@@ -17,6 +21,12 @@ impl Display for Config {
         writeln!(f)?;
         writeln!(f, "[Service]")?;
         writeln!(f, "User={}", self.user)?;
+        for assignment in self.environment.iter() {
+            writeln!(f, "Environment=\"{assignment}\"")?;
+        }
+        if let Some(path) = &self.environment_file {
+            writeln!(f, "EnvironmentFile=-{}", path.display())?;
+        }
         writeln!(f, "ExecStart={}", self.exec_start)?;
         writeln!(f, "RestartSec={}", self.restart_secs)?;
         writeln!(f, "Restart=on-failure")?;
@@ -46,8 +56,20 @@ impl Config {
             user: user.to_string(),
             exec_start: exec_start.join(" "),
             restart_secs,
+            environment: Vec::new(),
+            environment_file: None,
         }
     }
+
+    pub fn with_environment<K: Display, V: Display>(mut self, key: K, value: V) -> Self {
+        self.environment.push(format!("{key}={value}"));
+        self
+    }
+
+    pub fn with_environment_file(mut self, path: PathBuf) -> Self {
+        self.environment_file = Some(path);
+        self
+    }
 }
 
 pub fn enable<S: Service>(service: &S) -> Result<()> {
@@ -128,6 +150,23 @@ pub fn unit_state<S: Display>(service_name: S) -> std::result::Result<String, St
     }
 }
 
+/// Unit properties via `systemctl show` (does not require privileges)
+pub fn show(service_name: &str, properties: &[&str]) -> HashMap<String, String> {
+    cmd!(
+        "systemctl",
+        "show",
+        service_name,
+        format!("--property={}", properties.join(","))
+    )
+    .unchecked()
+    .read()
+    .unwrap_or_default()
+    .lines()
+    .filter_map(|line| line.split_once('='))
+    .map(|(key, value)| (key.to_string(), value.to_string()))
+    .collect()
+}
+
 pub fn is_failed_resp<S: Service>(service: &S) -> Result<String> {
     sudo!("systemctl", "is-failed", service.service_name())
         .unchecked()
@@ -153,6 +192,17 @@ pub fn service_path(service_name: &str) -> PathBuf {
     Path::new(SYSTEMD_SERVICE_PATH).join(format!("{service_name}.service"))
 }
 
+pub fn timer_path(timer_name: &str) -> PathBuf {
+    Path::new(SYSTEMD_SERVICE_PATH).join(format!("{timer_name}.timer"))
+}
+
+/// Drop-in file extending a unit without replacing it
+pub fn dropin_path(service_name: &str, name: &str) -> PathBuf {
+    Path::new(SYSTEMD_SERVICE_PATH)
+        .join(format!("{service_name}.service.d"))
+        .join(name)
+}
+
 pub fn create(config: Config) -> Result<()> {
     let service_path = service_path(&config.service);
     sudo::fs::write(service_path, config.to_string())?;
@@ -1,10 +1,14 @@
 use crate::imports::*;
+use chrono::{DateTime, NaiveDateTime, Utc};
 use rustls::pki_types::{CertificateDer, PrivateKeyDer};
 use std::fs;
 use std::io::BufReader;
 
+/// Warn when a certificate expires within this number of days
+pub const EXPIRY_WARNING_DAYS: i64 = 14;
+
 pub fn load_certs(filename: &str) -> Result<Vec<CertificateDer<'static>>> {
-    let certfile = fs::File::open(filename).expect("cannot open certificate file");
+    let certfile = fs::File::open(filename)?;
     let mut reader = BufReader::new(certfile);
     let certificate = rustls_pemfile::certs(&mut reader)
         .map(|result| result.map_err(Into::into))
@@ -13,7 +17,7 @@ pub fn load_certs(filename: &str) -> Result<Vec<CertificateDer<'static>>> {
 }
 
 pub fn load_private_key(filename: &str) -> Result<PrivateKeyDer<'static>> {
-    let keyfile = fs::File::open(filename).expect("cannot open private key file");
+    let keyfile = fs::File::open(filename)?;
     let mut reader = BufReader::new(keyfile);
 
     loop {
@@ -32,3 +36,174 @@ pub fn load_private_key(filename: &str) -> Result<PrivateKeyDer<'static>> {
         filename
     )))
 }
+
+#[derive(Debug, Clone)]
+pub struct CertificateInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub san: Vec<String>,
+    pub not_after: DateTime<Utc>,
+}
+
+impl CertificateInfo {
+    pub fn days_remaining(&self) -> i64 {
+        (self.not_after - Utc::now()).num_days()
+    }
+
+    pub fn is_expired(&self) -> bool {
+        self.not_after < Utc::now()
+    }
+
+    /// Check if the certificate is valid for the given server name
+    pub fn covers(&self, fqdn: &str) -> bool {
+        self.san.iter().any(|san| {
+            san == fqdn
+                || san.strip_prefix("*.").is_some_and(|suffix| {
+                    !fqdn.starts_with("*.")
+                        && fqdn
+                            .split_once('.')
+                            .is_some_and(|(_, parent)| parent == suffix)
+                })
+        })
+    }
+}
+
+/// Decode a DER certificate using `openssl x509`
+pub fn inspect(certificate: &CertificateDer<'_>) -> Result<CertificateInfo> {
+    let text = cmd!(
+        "openssl",
+        "x509",
+        "-inform",
+        "DER",
+        "-noout",
+        "-subject",
+        "-issuer",
+        "-enddate",
+        "-ext",
+        "subjectAltName",
+        "-nameopt",
+        "RFC2253"
+    )
+    .stdin_bytes(certificate.as_ref())
+    .read()?;
+
+    let mut subject = String::new();
+    let mut issuer = String::new();
+    let mut not_after = None;
+    let mut san = Vec::new();
+    let mut lines = text.lines().map(str::trim);
+    while let Some(line) = lines.next() {
+        if let Some(value) = line.strip_prefix("subject=") {
+            subject = value.trim().to_string();
+        } else if let Some(value) = line.strip_prefix("issuer=") {
+            issuer = value.trim().to_string();
+        } else if let Some(value) = line.strip_prefix("notAfter=") {
+            // e.g. `Jan  1 00:00:00 2025 GMT`
+            let value = value.trim().trim_end_matches("GMT").trim();
+            let value = value.split_whitespace().collect::<Vec<_>>().join(" ");
+            not_after = NaiveDateTime::parse_from_str(&value, "%b %d %H:%M:%S %Y")
+                .ok()
+                .map(|ts| ts.and_utc());
+        } else if line.starts_with("X509v3 Subject Alternative Name") {
+            if let Some(names) = lines.next() {
+                san = names
+                    .split(',')
+                    .filter_map(|name| {
+                        let name = name.trim();
+                        name.strip_prefix("DNS:")
+                            .or_else(|| name.strip_prefix("IP Address:"))
+                            .map(|name| name.to_lowercase())
+                    })
+                    .collect();
+            }
+        }
+    }
+
+    let not_after =
+        not_after.ok_or_else(|| Error::custom("Unable to determine certificate expiry date"))?;
+
+    Ok(CertificateInfo {
+        subject,
+        issuer,
+        san,
+        not_after,
+    })
+}
+
+/// Decode all certificates in a PEM certificate chain file
+pub fn inspect_chain(filename: &str) -> Result<Vec<CertificateInfo>> {
+    let certificates = load_certs(filename)?;
+    if certificates.is_empty() {
+        return Err(Error::custom(format!(
+            "No certificates found in {filename}"
+        )));
+    }
+    certificates.iter().map(inspect).collect()
+}
+
+/// Check if the private key corresponds to the (leaf) certificate public key
+pub fn key_matches(key: &str, crt: &str) -> Result<bool> {
+    let certificate_key = cmd!("openssl", "x509", "-noout", "-pubkey", "-in", crt).read()?;
+    let private_key = cmd!("openssl", "pkey", "-pubout", "-in", key).read()?;
+    Ok(certificate_key.trim() == private_key.trim())
+}
+
+/// Inspection of the certificates configured in NGINX
+#[derive(Debug, Clone)]
+pub struct CertsStatus {
+    pub chain: Vec<CertificateInfo>,
+    pub key_matches: bool,
+}
+
+impl CertsStatus {
+    pub fn try_new(certs: &nginx::Certs) -> Result<Self> {
+        let chain = inspect_chain(&certs.crt)?;
+        let key_matches = key_matches(&certs.key, &certs.crt)?;
+        Ok(Self { chain, key_matches })
+    }
+
+    pub fn leaf(&self) -> &CertificateInfo {
+        &self.chain[0]
+    }
+
+    /// Issues with the certificate for the served FQDNs
+    pub fn issues(&self, fqdns: &[String]) -> Vec<String> {
+        let mut issues = Vec::new();
+        let leaf = self.leaf();
+
+        if !self.key_matches {
+            issues.push("SSL private key does not match the certificate".to_string());
+        }
+
+        for (n, info) in self.chain.iter().enumerate() {
+            let which = if n == 0 {
+                "SSL certificate".to_string()
+            } else {
+                format!("SSL chain certificate `{}`", info.subject)
+            };
+            if info.is_expired() {
+                issues.push(format!("{which} has expired on {}", info.not_after));
+            } else if info.days_remaining() < EXPIRY_WARNING_DAYS {
+                issues.push(format!(
+                    "{which} expires in {} days ({})",
+                    info.days_remaining(),
+                    info.not_after
+                ));
+            }
+        }
+
+        let uncovered = fqdns
+            .iter()
+            .filter(|fqdn| fqdn.as_str() != "_" && !leaf.covers(fqdn))
+            .cloned()
+            .collect::<Vec<_>>();
+        if !uncovered.is_empty() {
+            issues.push(format!(
+                "SSL certificate does not cover: {}",
+                uncovered.join(" ")
+            ));
+        }
+
+        issues
+    }
+}
@@ -0,0 +1,483 @@
+use crate::imports::*;
+use std::collections::VecDeque;
+use std::io::Write;
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use termion::event::Key;
+use termion::input::TermRead;
+use termion::raw::IntoRawMode;
+use termion::screen::IntoAlternateScreen;
+
+/// Dashboard refresh interval
+const REFRESH: Duration = Duration::from_secs(1);
+/// Interval between journal reads (requires `sudo`)
+const JOURNAL_REFRESH: Duration = Duration::from_secs(5);
+/// Journal lines scanned per service for warnings
+const JOURNAL_LINES: usize = 200;
+/// Warnings shown below the service table
+const WARNINGS: usize = 8;
+/// Wait for input before returning to the refresh loop
+const KEY_POLL: Duration = Duration::from_millis(50);
+
+type HealthMap = HashMap<String, std::result::Result<health::Health, String>>;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+enum View {
+    Services,
+    Logs,
+}
+
+struct Row {
+    service: ServiceDetail,
+    state: String,
+    pid: Option<u32>,
+    cpu: Option<f32>,
+    rss: Option<u64>,
+}
+
+struct Dashboard {
+    services: Vec<ServiceDetail>,
+    rows: Vec<Row>,
+    selected: usize,
+    view: View,
+    system: sysinfo::System,
+    networks: sysinfo::Networks,
+    network_refreshed: Instant,
+    /// Bytes per second received and transmitted on all non-loopback interfaces
+    throughput: (f64, f64),
+    health: Arc<Mutex<HealthMap>>,
+    warnings: Vec<String>,
+    logs: Vec<String>,
+    journal_refreshed: Option<Instant>,
+    message: Option<String>,
+}
+
+/// Query node health in the background so that unresponsive
+/// nodes do not stall the dashboard refresh
+fn spawn_health_poller(
+    targets: Vec<(String, SocketAddr)>,
+    running: Arc<AtomicBool>,
+) -> Arc<Mutex<HealthMap>> {
+    let health = Arc::new(Mutex::new(HashMap::new()));
+    let shared = health.clone();
+    std::thread::spawn(move || {
+        while running.load(Ordering::Relaxed) {
+            for (service, addr) in targets.iter() {
+                let result = health::fetch(*addr, health::TIMEOUT).map_err(|err| err.to_string());
+                shared.lock().unwrap().insert(service.clone(), result);
+            }
+            std::thread::sleep(REFRESH);
+        }
+    });
+    health
+}
+
+/// Journal lines of a unit prefixed by ISO timestamps (sortable across units)
+fn journal(service: &str, lines: usize) -> Vec<String> {
+    sudo!(
+        "journalctl",
+        "-u",
+        service,
+        "-n",
+        lines.to_string(),
+        "-o",
+        "short-iso",
+        "--no-pager"
+    )
+    .unchecked()
+    .read()
+    .unwrap_or_default()
+    .lines()
+    .filter(|line| !line.starts_with("-- "))
+    .map(String::from)
+    .collect()
+}
+
+fn is_warning(line: &str) -> bool {
+    ["WARN", "ERROR", "Failed", "failed"]
+        .iter()
+        .any(|pattern| line.contains(pattern))
+}
+
+fn truncate(line: &str, width: usize) -> String {
+    line.chars().take(width).collect()
+}
+
+/// Terminal size, falling back to 80x24 when it cannot be determined
+fn terminal_size() -> (usize, usize) {
+    termion::terminal_size()
+        .ok()
+        .filter(|(width, height)| *width > 0 && *height > 0)
+        .map(|(width, height)| (width as usize, height as usize))
+        .unwrap_or((80, 24))
+}
+
+fn rate(bytes_per_sec: f64) -> String {
+    format!("{}/s", as_data_size(bytes_per_sec, false))
+}
+
+impl Dashboard {
+    fn new(ctx: &Context, health: Arc<Mutex<HealthMap>>) -> Self {
+        Self {
+            services: ctx.active_services(),
+            rows: Vec::new(),
+            selected: 0,
+            view: View::Services,
+            system: sysinfo::System::new(),
+            networks: sysinfo::Networks::new_with_refreshed_list(),
+            network_refreshed: Instant::now(),
+            throughput: (0.0, 0.0),
+            health,
+            warnings: Vec::new(),
+            logs: Vec::new(),
+            journal_refreshed: None,
+            message: None,
+        }
+    }
+
+    fn selected(&self) -> Option<&ServiceDetail> {
+        self.services.get(self.selected)
+    }
+
+    fn refresh(&mut self) {
+        use sysinfo::{Pid, ProcessRefreshKind};
+
+        self.rows = self
+            .services
+            .iter()
+            .map(|service| {
+                let properties =
+                    systemd::show(&service.name, &["ActiveState", "SubState", "MainPID"]);
+                let state = format!(
+                    "{}/{}",
+                    properties
+                        .get("ActiveState")
+                        .map(String::as_str)
+                        .unwrap_or("unknown"),
+                    properties
+                        .get("SubState")
+                        .map(String::as_str)
+                        .unwrap_or("unknown")
+                );
+                let pid = properties
+                    .get("MainPID")
+                    .and_then(|pid| pid.parse::<u32>().ok())
+                    .filter(|pid| *pid > 0);
+                let (cpu, rss) = pid
+                    .and_then(|pid| {
+                        let pid = Pid::from_u32(pid);
+                        self.system.refresh_process_specifics(
+                            pid,
+                            ProcessRefreshKind::new().with_cpu().with_memory(),
+                        );
+                        self.system
+                            .process(pid)
+                            .map(|process| (Some(process.cpu_usage()), Some(process.memory())))
+                    })
+                    .unwrap_or_default();
+                Row {
+                    service: service.clone(),
+                    state,
+                    pid,
+                    cpu,
+                    rss,
+                }
+            })
+            .collect();
+
+        self.networks.refresh();
+        let elapsed = self.network_refreshed.elapsed().as_secs_f64().max(0.001);
+        self.network_refreshed = Instant::now();
+        let (rx, tx) = self
+            .networks
+            .iter()
+            .filter(|(name, _)| name.as_str() != "lo")
+            .fold((0, 0), |(rx, tx), (_, data)| {
+                (rx + data.received(), tx + data.transmitted())
+            });
+        self.throughput = (rx as f64 / elapsed, tx as f64 / elapsed);
+
+        if self
+            .journal_refreshed
+            .is_none_or(|refreshed| refreshed.elapsed() >= JOURNAL_REFRESH)
+        {
+            self.refresh_journal();
+        }
+    }
+
+    fn refresh_journal(&mut self) {
+        self.journal_refreshed = Some(Instant::now());
+        match self.view {
+            View::Services => {
+                let mut warnings = self
+                    .services
+                    .iter()
+                    .flat_map(|service| journal(&service.name, JOURNAL_LINES))
+                    .filter(|line| is_warning(line))
+                    .collect::<Vec<_>>();
+                warnings.sort();
+                let skip = warnings.len().saturating_sub(WARNINGS);
+                self.warnings = warnings.split_off(skip);
+            }
+            View::Logs => {
+                let (_, height) = terminal_size();
+                if let Some(service) = self.selected() {
+                    self.logs = journal(&service.name, height);
+                }
+            }
+        }
+    }
+
+    fn render_services(&self, width: usize, height: usize) -> Vec<String> {
+        let health = self.health.lock().unwrap().clone();
+
+        let mut lines = vec![
+            format!(
+                "{} · {} · net ↓ {} ↑ {}",
+                style(format!("kHOST v{} top", khost::VERSION))
+                    .cyan()
+                    .bright(),
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+                rate(self.throughput.0),
+                rate(self.throughput.1)
+            ),
+            String::new(),
+            style(truncate(
+                &format!(
+                    "  {:<24} {:<18} {:>8} {:>7} {:>10}  {}",
+                    "SERVICE", "STATE", "PID", "CPU", "RSS", "NODE"
+                ),
+                width,
+            ))
+            .bold()
+            .to_string(),
+        ];
+
+        for (index, row) in self.rows.iter().enumerate() {
+            let node = match health.get(&row.service.name) {
+                Some(Ok(health)) => format!(
+                    "{} DAA {} · peers {}/{}",
+                    if health.is_synced {
+                        "synced"
+                    } else {
+                        "syncing"
+                    },
+                    health.virtual_daa_score,
+                    health.inbound_peers,
+                    health.outbound_peers
+                ),
+                Some(Err(err)) => format!("wRPC: {err}"),
+                None => String::new(),
+            };
+            let text = format!(
+                "{} {:<24} {:<18} {:>8} {:>7} {:>10}  {}",
+                if index == self.selected { ">" } else { " " },
+                row.service.name,
+                row.state,
+                row.pid.map(|pid| pid.to_string()).unwrap_or_default(),
+                row.cpu.map(|cpu| format!("{cpu:1.1}%")).unwrap_or_default(),
+                row.rss
+                    .map(|rss| as_data_size(rss as f64, false))
+                    .unwrap_or_default(),
+                node
+            );
+            let text = truncate(&text, width);
+            let text = if row.state.starts_with("active/") {
+                style(text).green()
+            } else if row.state.starts_with("failed/") {
+                style(text).red().bright()
+            } else {
+                style(text).yellow()
+            };
+            let text = if index == self.selected {
+                text.reverse()
+            } else {
+                text
+            };
+            lines.push(text.to_string());
+        }
+
+        lines.push(String::new());
+        lines.push(style("Recent warnings").bold().to_string());
+        if self.warnings.is_empty() {
+            lines.push(style("none").dim().to_string());
+        }
+        for warning in self.warnings.iter() {
+            lines.push(style(truncate(warning, width)).yellow().to_string());
+        }
+
+        lines.truncate(height.saturating_sub(2));
+        lines
+    }
+
+    fn render_logs(&self, width: usize, height: usize) -> Vec<String> {
+        let title = self
+            .selected()
+            .map(|service| service.name.clone())
+            .unwrap_or_default();
+        let mut lines = vec![style(format!("{title} logs")).cyan().bright().to_string()];
+        let available = height.saturating_sub(3);
+        let skip = self.logs.len().saturating_sub(available);
+        lines.extend(
+            self.logs
+                .iter()
+                .skip(skip)
+                .map(|line| truncate(line, width)),
+        );
+        lines
+    }
+
+    fn render<W: Write>(&self, out: &mut W) -> Result<()> {
+        let (width, height) = terminal_size();
+
+        let lines = match self.view {
+            View::Services => self.render_services(width, height),
+            View::Logs => self.render_logs(width, height),
+        };
+        let keys = match self.view {
+            View::Services => "[↑/↓] select  [r] restart  [l] logs  [q] quit",
+            View::Logs => "[l/esc] back  [q] quit",
+        };
+
+        write!(
+            out,
+            "{}{}",
+            termion::clear::All,
+            termion::cursor::Goto(1, 1)
+        )?;
+        for line in lines {
+            write!(out, "{line}\r\n")?;
+        }
+        write!(out, "{}", termion::cursor::Goto(1, height as u16))?;
+        write!(out, "{}", style(truncate(keys, width)).dim())?;
+        if let Some(message) = &self.message {
+            write!(out, "  {}", style(message).yellow().bright())?;
+        }
+        out.flush()?;
+        Ok(())
+    }
+
+    fn restart_selected(&mut self) {
+        let Some(service) = self.selected().cloned() else {
+            return;
+        };
+        self.message = Some(match sudo!("systemctl", "restart", &service.name).run() {
+            Ok(()) => format!("Restarted '{}'", service.name),
+            Err(err) => format!("Unable to restart '{}': {err}", service.name),
+        });
+    }
+
+    /// Returns `false` when the dashboard should exit
+    fn handle(&mut self, key: Key) -> bool {
+        match (self.view, key) {
+            (_, Key::Char('q')) | (_, Key::Ctrl('c')) => return false,
+            (View::Services, Key::Esc) => return false,
+            (View::Services, Key::Up) => self.selected = self.selected.saturating_sub(1),
+            (View::Services, Key::Down) => {
+                self.selected = (self.selected + 1).min(self.services.len().saturating_sub(1))
+            }
+            (View::Services, Key::Char('r')) => self.restart_selected(),
+            (View::Services, Key::Char('l')) => {
+                self.view = View::Logs;
+                self.message = None;
+                self.refresh_journal();
+            }
+            (View::Logs, Key::Char('l')) | (View::Logs, Key::Esc) => {
+                self.view = View::Services;
+                self.refresh_journal();
+            }
+            _ => {}
+        }
+        true
+    }
+}
+
+/// Keys read from stdin on the calling thread; unlike `termion::async_stdin()`
+/// nothing keeps reading stdin once the dashboard is closed
+#[derive(Default)]
+struct Keys {
+    pending: VecDeque<std::io::Result<Key>>,
+}
+
+impl Iterator for Keys {
+    type Item = std::io::Result<Key>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pending.is_empty() {
+            let mut fd = libc::pollfd {
+                fd: libc::STDIN_FILENO,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            // SAFETY: a single valid pollfd is passed for the duration of the call
+            let ready = unsafe { libc::poll(&mut fd, 1, KEY_POLL.as_millis() as libc::c_int) };
+            if ready > 0 && fd.revents & libc::POLLIN != 0 {
+                // read the descriptor directly; buffered `Stdin` could hold
+                // bytes that `poll` no longer reports
+                let mut buffer = [0u8; 64];
+                // SAFETY: the buffer is valid for writes of its length
+                let length = unsafe {
+                    libc::read(libc::STDIN_FILENO, buffer.as_mut_ptr().cast(), buffer.len())
+                };
+                match length {
+                    0 => return Some(Err(std::io::ErrorKind::UnexpectedEof.into())),
+                    length if length < 0 => return Some(Err(std::io::Error::last_os_error())),
+                    _ => {}
+                }
+                self.pending.extend(buffer[..length as usize].keys());
+            }
+        }
+        self.pending.pop_front()
+    }
+}
+
+fn event_loop<K, W>(dashboard: &mut Dashboard, keys: &mut K, out: &mut W) -> Result<()>
+where
+    K: Iterator<Item = std::io::Result<Key>>,
+    W: Write,
+{
+    loop {
+        dashboard.refresh();
+        dashboard.render(out)?;
+
+        let next = Instant::now() + REFRESH;
+        while Instant::now() < next {
+            match keys.next() {
+                Some(Ok(key)) => {
+                    if !dashboard.handle(key) {
+                        return Ok(());
+                    }
+                    dashboard.render(out)?;
+                }
+                Some(Err(_)) => std::thread::sleep(KEY_POLL),
+                None => {}
+            }
+        }
+    }
+}
+
+/// Full-screen dashboard refreshing every second (`khost top`)
+pub fn run(ctx: &Context) -> Result<()> {
+    let targets = kaspad::active_configs(ctx)
+        .filter_map(|config| {
+            config
+                .wrpc_json_address()
+                .map(|addr| (config.service_name(), addr))
+        })
+        .collect::<Vec<_>>();
+    let running = Arc::new(AtomicBool::new(true));
+    let health = spawn_health_poller(targets, running.clone());
+
+    let mut dashboard = Dashboard::new(ctx, health);
+    let mut keys = Keys::default();
+    let mut screen = std::io::stdout().into_raw_mode()?.into_alternate_screen()?;
+    write!(screen, "{}", termion::cursor::Hide)?;
+
+    let result = event_loop(&mut dashboard, &mut keys, &mut screen);
+
+    running.store(false, Ordering::Relaxed);
+    write!(screen, "{}", termion::cursor::Show)?;
+    screen.flush()?;
+    result
+}
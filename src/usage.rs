@@ -0,0 +1,231 @@
+use crate::imports::*;
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+
+/// Number of client addresses listed in the report
+const TOP_CLIENTS: usize = 10;
+
+/// A single line of the kHOST structured access log
+#[derive(Debug, Clone, Deserialize)]
+pub struct Entry {
+    /// ISO 8601 local time (`$time_iso8601`)
+    pub time: String,
+    pub remote_addr: String,
+    #[serde(default)]
+    pub uri: String,
+    pub status: u16,
+    #[serde(default)]
+    pub bytes_sent: u64,
+    #[serde(default)]
+    pub request_length: u64,
+    #[serde(default)]
+    pub upgrade: String,
+}
+
+impl Entry {
+    pub fn timestamp(&self) -> Option<DateTime<FixedOffset>> {
+        DateTime::parse_from_rfc3339(&self.time).ok()
+    }
+
+    pub fn is_websocket(&self) -> bool {
+        self.status == 101 || self.upgrade.eq_ignore_ascii_case("websocket")
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum Window {
+    Hour,
+    Day,
+    Week,
+}
+
+impl Window {
+    pub fn duration(&self) -> Duration {
+        match self {
+            Window::Hour => Duration::hours(1),
+            Window::Day => Duration::days(1),
+            Window::Week => Duration::weeks(1),
+        }
+    }
+}
+
+impl Display for Window {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Window::Hour => write!(f, "last hour"),
+            Window::Day => write!(f, "last 24 hours"),
+            Window::Week => write!(f, "last 7 days"),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Traffic {
+    pub requests: u64,
+    pub websockets: u64,
+    pub bytes_in: u64,
+    pub bytes_out: u64,
+}
+
+impl Traffic {
+    fn add(&mut self, entry: &Entry) {
+        self.requests += 1;
+        if entry.is_websocket() {
+            self.websockets += 1;
+        }
+        self.bytes_in += entry.request_length;
+        self.bytes_out += entry.bytes_sent;
+    }
+}
+
+impl Display for Traffic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} requests, {} websockets, {} in, {} out",
+            self.requests,
+            self.websockets,
+            as_data_size(self.bytes_in as f64, false),
+            as_data_size(self.bytes_out as f64, false)
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Usage {
+    pub window: Window,
+    pub total: Traffic,
+    pub paths: Vec<(String, Traffic)>,
+    pub status: Vec<(u16, u64)>,
+    pub clients: Vec<(String, Traffic)>,
+}
+
+impl Usage {
+    /// Aggregate access log entries within the window by proxy location path
+    pub fn new(window: Window, paths: &[String], entries: impl Iterator<Item = Entry>) -> Self {
+        let since = Utc::now() - window.duration();
+
+        // longest prefix wins, e.g. `/mainnet/grpc` before `/mainnet`
+        let mut locations = paths.to_vec();
+        locations.sort_by_key(|path| std::cmp::Reverse(path.len()));
+
+        let mut total = Traffic::default();
+        let mut by_path: HashMap<String, Traffic> = HashMap::new();
+        let mut by_status: HashMap<u16, u64> = HashMap::new();
+        let mut by_client: HashMap<String, Traffic> = HashMap::new();
+
+        for entry in entries.filter(|entry| entry.timestamp().is_some_and(|time| time >= since)) {
+            let path = locations
+                .iter()
+                .find(|path| entry.uri.starts_with(path.as_str()))
+                .cloned()
+                .unwrap_or_else(|| "other".to_string());
+
+            total.add(&entry);
+            by_path.entry(path).or_default().add(&entry);
+            *by_status.entry(entry.status).or_default() += 1;
+            by_client
+                .entry(entry.remote_addr.clone())
+                .or_default()
+                .add(&entry);
+        }
+
+        let mut paths = by_path.into_iter().collect::<Vec<_>>();
+        paths.sort_by_key(|(_, traffic)| std::cmp::Reverse(traffic.requests));
+        let mut status = by_status.into_iter().collect::<Vec<_>>();
+        status.sort();
+        let mut clients = by_client.into_iter().collect::<Vec<_>>();
+        clients.sort_by_key(|(_, traffic)| std::cmp::Reverse(traffic.requests));
+        clients.truncate(TOP_CLIENTS);
+
+        Self {
+            window,
+            total,
+            paths,
+            status,
+            clients,
+        }
+    }
+}
+
+impl Display for Usage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut rows = vec![
+            Content::field("Window:", self.window),
+            Content::field("Total:", &self.total),
+            Content::separator(),
+        ];
+        for (path, traffic) in self.paths.iter() {
+            rows.push(Content::field(path, traffic));
+        }
+        rows.push(Content::separator());
+        for (status, count) in self.status.iter() {
+            rows.push(Content::field(format!("HTTP {status}:"), count));
+        }
+        rows.push(Content::separator());
+        for (client, traffic) in self.clients.iter() {
+            rows.push(Content::field(client, traffic));
+        }
+
+        write!(f, "{}", content(rows))
+    }
+}
+
+/// Parse access log lines, skipping lines that are not in the kHOST format
+pub fn parse(text: &str) -> impl Iterator<Item = Entry> + '_ {
+    text.lines()
+        .filter_map(|line| serde_json::from_str::<Entry>(line).ok())
+}
+
+/// Number of rotated logs (daily rotation) needed to cover the window
+fn rotations(window: Window) -> usize {
+    window.duration().num_days() as usize + 1
+}
+
+/// Read the current and rotated access logs, oldest first
+/// (NGINX logs are readable by `adm` group members only)
+pub fn read_log(window: Window) -> Result<String> {
+    let mut text = String::new();
+    for n in (1..=rotations(window)).rev() {
+        let plain = format!("{}.{n}", nginx::ACCESS_LOG);
+        let compressed = format!("{plain}.gz");
+        if Path::new(&plain).exists() {
+            text.push_str(&sudo!("cat", plain).read()?);
+        } else if Path::new(&compressed).exists() {
+            text.push_str(&sudo!("zcat", compressed).read()?);
+        } else {
+            continue;
+        }
+        text.push('\n');
+    }
+    if Path::new(nginx::ACCESS_LOG).exists() {
+        text.push_str(&sudo!("cat", nginx::ACCESS_LOG).read()?);
+    }
+    Ok(text)
+}
+
+pub fn report(ctx: &Context) -> Result<()> {
+    let window = cliclack::select("Select time window")
+        .item(Window::Hour, Window::Hour, "")
+        .item(Window::Day, Window::Day, "")
+        .item(Window::Week, Window::Week, "")
+        .interact()?;
+
+    let text = read_log(window)?;
+    if text.trim().is_empty() {
+        log::warning(format!("No entries found in `{}`", nginx::ACCESS_LOG))?;
+        return Ok(());
+    }
+
+    let paths = ctx
+        .proxy_configs(ctx)
+        .into_iter()
+        .map(|proxy| proxy.path)
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+
+    let usage = Usage::new(window, &paths, parse(&text));
+    log::info(usage)?;
+
+    Ok(())
+}
@@ -0,0 +1,459 @@
+use crate::imports::*;
+use chrono::{DateTime, Duration as ChronoDuration, Local};
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+pub const SERVICE_NAME: &str = "khost-watchdog";
+
+/// Allows the watchdog (running as the kHOST user) to restart node units
+const SUDOERS_PATH: &str = "/etc/sudoers.d/khost-watchdog";
+const SYSTEMCTL: &str = "/usr/bin/systemctl";
+
+/// Maximum backoff between consecutive restarts of the same node
+const MAX_BACKOFF: Duration = Duration::from_secs(6 * 3600);
+/// Number of history records kept on disk
+const HISTORY_LIMIT: usize = 1000;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Config {
+    pub enabled: bool,
+    /// Seconds between node polls
+    pub interval: u64,
+    /// Seconds without DAA score (or header/block) progress before restarting a synced node
+    pub stall_timeout: u64,
+    /// Seconds without connected peers before restarting a synced node
+    pub peers_timeout: u64,
+    /// Initial seconds to wait after a restart before restarting again (doubles each time)
+    pub backoff: u64,
+    /// Maximum restarts per node within 24 hours
+    pub daily_cap: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval: 30,
+            stall_timeout: 600,
+            peers_timeout: 600,
+            backoff: 900,
+            daily_cap: 4,
+        }
+    }
+}
+
+impl Service for Config {
+    fn service_title(&self) -> String {
+        "kHOST watchdog".to_string()
+    }
+
+    fn service_name(&self) -> String {
+        SERVICE_NAME.to_string()
+    }
+
+    fn kind(&self) -> ServiceKind {
+        ServiceKind::Watchdog
+    }
+
+    fn origin(&self) -> Option<Origin> {
+        None
+    }
+
+    fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    fn managed(&self) -> bool {
+        true
+    }
+
+    fn proxy_config(&self, _ctx: &Context) -> Option<Vec<ProxyConfig>> {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Eq, PartialEq)]
+pub enum Reason {
+    /// DAA score, header and block counts did not advance
+    Stalled,
+    /// No connected peers
+    NoPeers,
+    /// wRPC interface is not responding
+    Unresponsive,
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Reason::Stalled => write!(f, "no sync progress"),
+            Reason::NoPeers => write!(f, "no peers"),
+            Reason::Unresponsive => write!(f, "wRPC not responding"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Eq, PartialEq)]
+pub enum Outcome {
+    Restarted,
+    Failed(String),
+    /// Restart skipped because the daily cap was reached
+    Capped,
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Restarted => write!(f, "restarted"),
+            Outcome::Failed(err) => write!(f, "restart failed: {err}"),
+            Outcome::Capped => write!(f, "daily restart cap reached"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Intervention {
+    pub time: DateTime<Local>,
+    pub service: String,
+    pub reason: Reason,
+    pub outcome: Outcome,
+}
+
+impl Display for Intervention {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: {} ({})",
+            self.time.format("%Y-%m-%d %H:%M:%S"),
+            self.service,
+            self.outcome,
+            self.reason
+        )
+    }
+}
+
+pub fn history_filename() -> PathBuf {
+    data_folder().join("watchdog-history.jsonl")
+}
+
+pub fn history() -> Vec<Intervention> {
+    fs::read_to_string(history_filename())
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+fn record(intervention: &Intervention) -> Result<()> {
+    println!("watchdog: {intervention}");
+
+    let mut history = history();
+    if history.len() >= HISTORY_LIMIT {
+        history.drain(..history.len() + 1 - HISTORY_LIMIT);
+        history.push(intervention.clone());
+        let text = history
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<std::result::Result<Vec<_>, _>>()?
+            .join("\n");
+        fs::write(history_filename(), text + "\n")?;
+    } else {
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(history_filename())?;
+        writeln!(file, "{}", serde_json::to_string(intervention)?)?;
+    }
+    Ok(())
+}
+
+/// Per-node progress tracking
+struct Node {
+    progress: (u64, u64, u64),
+    last_progress: Instant,
+    last_peers: Instant,
+    last_response: Instant,
+    backoff: Duration,
+    next_restart: Instant,
+    capped: bool,
+}
+
+impl Node {
+    fn new(config: &Config) -> Self {
+        let now = Instant::now();
+        Self {
+            progress: (0, 0, 0),
+            last_progress: now,
+            last_peers: now,
+            last_response: now,
+            backoff: Duration::from_secs(config.backoff),
+            next_restart: now,
+            capped: false,
+        }
+    }
+
+    fn update(&mut self, health: &std::result::Result<health::Health, String>) {
+        let now = Instant::now();
+        if let Ok(health) = health {
+            self.last_response = now;
+            let progress = (
+                health.virtual_daa_score,
+                health.header_count,
+                health.block_count,
+            );
+            // IBD phases may not move the tracked counters for a long time,
+            // so stall and peer checks apply to synced nodes only
+            if !health.is_synced || progress != self.progress {
+                self.progress = progress;
+                self.last_progress = now;
+            }
+            if !health.is_synced || health.inbound_peers + health.outbound_peers > 0 {
+                self.last_peers = now;
+            }
+        }
+    }
+
+    fn check(&self, config: &Config) -> Option<Reason> {
+        let timeout = |secs| Duration::from_secs(secs);
+        if self.last_response.elapsed() > timeout(config.stall_timeout) {
+            Some(Reason::Unresponsive)
+        } else if self.last_progress.elapsed() > timeout(config.stall_timeout) {
+            Some(Reason::Stalled)
+        } else if self.last_peers.elapsed() > timeout(config.peers_timeout) {
+            Some(Reason::NoPeers)
+        } else {
+            None
+        }
+    }
+
+    fn restarted(&mut self, config: &Config) {
+        *self = Self {
+            progress: self.progress,
+            backoff: (self.backoff * 2).min(MAX_BACKOFF),
+            next_restart: Instant::now() + self.backoff,
+            ..Self::new(config)
+        };
+    }
+}
+
+fn restarts_today(history: &[Intervention], service: &str) -> usize {
+    let since = Local::now() - ChronoDuration::days(1);
+    history
+        .iter()
+        .filter(|record| {
+            record.service == service && record.time > since && record.outcome == Outcome::Restarted
+        })
+        .count()
+}
+
+/// Unit state query that does not require privileges
+fn is_active(service: &str) -> bool {
+    cmd!("systemctl", "is-active", service)
+        .unchecked()
+        .read()
+        .is_ok_and(|state| state.trim() == "active")
+}
+
+/// Restart a node unit permitted by [`install_sudoers`] rules
+pub fn restart(service: &str) -> Result<()> {
+    cmd!("sudo", "-n", SYSTEMCTL, "restart", service).run()
+}
+
+/// Poll node instances until the process is terminated (`khost watchdog`)
+pub fn run(ctx: &Context) -> Result<()> {
+    let config = &ctx.config.watchdog;
+    println!(
+        "kHOST watchdog polling every {}s (stall timeout {}s, peers timeout {}s)",
+        config.interval, config.stall_timeout, config.peers_timeout
+    );
+
+    let mut nodes: HashMap<String, Node> = HashMap::new();
+    loop {
+        let health = health::detect(ctx);
+        for (service, health) in health.iter() {
+            // systemd handles units that are not running
+            if !is_active(service) {
+                nodes.remove(service);
+                continue;
+            }
+
+            let node = nodes
+                .entry(service.clone())
+                .or_insert_with(|| Node::new(config));
+            node.update(health);
+
+            let Some(reason) = node.check(config) else {
+                // healthy beyond the backoff period
+                if Instant::now() >= node.next_restart {
+                    node.backoff = Duration::from_secs(config.backoff);
+                    node.capped = false;
+                }
+                continue;
+            };
+            if Instant::now() < node.next_restart {
+                continue;
+            }
+
+            let outcome = if restarts_today(&history(), service) >= config.daily_cap {
+                if node.capped {
+                    continue;
+                }
+                node.capped = true;
+                Outcome::Capped
+            } else {
+                match restart(service) {
+                    Ok(()) => Outcome::Restarted,
+                    Err(err) => Outcome::Failed(err.to_string()),
+                }
+            };
+
+            if outcome != Outcome::Capped {
+                node.restarted(config);
+            }
+
+            let intervention = Intervention {
+                time: Local::now(),
+                service: service.clone(),
+                reason,
+                outcome,
+            };
+            record(&intervention)?;
+            alerts::notify(
+                ctx,
+                alerts::Alert::new(
+                    alerts::Kind::WatchdogRestart,
+                    service,
+                    format!("{} ({})", intervention.outcome, intervention.reason),
+                ),
+            );
+        }
+
+        std::thread::sleep(Duration::from_secs(config.interval.max(1)));
+    }
+}
+
+/// Permit restarting any possible node unit without a password
+fn sudoers(ctx: &Context, comment: &str) -> String {
+    let mut rules = vec![format!("# {comment}: restart Kaspa node units")];
+    for network in Network::iter() {
+        let units = std::iter::once(format!("kaspa-{network}"))
+            .chain((1..=kaspad::MAX_INSTANCES).map(|n| format!("kaspa-{network}-{n}")));
+        for unit in units {
+            rules.push(format!(
+                "{} ALL=(root) NOPASSWD: {SYSTEMCTL} restart {unit}",
+                ctx.username
+            ));
+        }
+    }
+    rules.join("\n") + "\n"
+}
+
+/// Install a sudoers file allowing background services to restart nodes
+pub fn install_sudoers(ctx: &Context, path: &str, comment: &str) -> Result<()> {
+    sudo::fs::write_with_permissions(path, sudoers(ctx, comment), "root:root", "440")?;
+    if let Err(err) = sudo!("visudo", "-cf", path).run() {
+        sudo::fs::remove_file(path)?;
+        return Err(err);
+    }
+    Ok(())
+}
+
+pub fn create_systemd_unit(ctx: &Context, config: &Config) -> Result<()> {
+    let exec_start = vec![
+        khost::binary()?.display().to_string(),
+        "watchdog".to_string(),
+    ];
+
+    let unit_config = systemd::Config::new(config, "kHOST watchdog", &ctx.username, exec_start, 5);
+
+    systemd::create(unit_config)?;
+
+    Ok(())
+}
+
+pub fn reconfigure(ctx: &Context) -> Result<()> {
+    let config = &ctx.config.watchdog;
+
+    if config.enabled() {
+        step("Configuring 'khost-watchdog'", || {
+            install_sudoers(ctx, SUDOERS_PATH, "kHOST watchdog")?;
+            create_systemd_unit(ctx, config)?;
+            systemd::daemon_reload()?;
+            systemd::enable(config)?;
+            systemd::restart(config)?;
+            Ok(())
+        })?;
+    } else if systemd::exists(config) {
+        step("Removing 'khost-watchdog'", || {
+            systemd::stop(config)?;
+            systemd::disable(config)?;
+            systemd::remove(config)?;
+            systemd::daemon_reload()?;
+            if Path::new(SUDOERS_PATH).exists() {
+                sudo::fs::remove_file(SUDOERS_PATH)?;
+            }
+            Ok(())
+        })?;
+    }
+
+    Ok(())
+}
+
+fn ask_secs(prompt: &str, current: u64) -> Result<u64> {
+    let value: String = cliclack::input(prompt)
+        .default_input(&current.to_string())
+        .validate(|input: &String| match input.parse::<u64>() {
+            Ok(value) if value > 0 => Ok(()),
+            _ => Err("Please enter a positive number".to_string()),
+        })
+        .interact()?;
+    Ok(value.parse()?)
+}
+
+pub fn configure(ctx: &mut Context) -> Result<()> {
+    let config = &ctx.config.watchdog;
+    let interval = ask_secs("Polling interval (seconds):", config.interval)?;
+    let stall_timeout = ask_secs(
+        "Restart after no sync progress for (seconds):",
+        config.stall_timeout,
+    )?;
+    let peers_timeout = ask_secs(
+        "Restart after no connected peers for (seconds):",
+        config.peers_timeout,
+    )?;
+    let backoff = ask_secs(
+        "Minimum delay between restarts (seconds, doubles after each restart):",
+        config.backoff,
+    )?;
+    let daily_cap = ask_secs(
+        "Maximum restarts per node within 24 hours:",
+        config.daily_cap as u64,
+    )?;
+
+    let config = &mut ctx.config.watchdog;
+    config.interval = interval;
+    config.stall_timeout = stall_timeout;
+    config.peers_timeout = peers_timeout;
+    config.backoff = backoff;
+    config.daily_cap = daily_cap as usize;
+    ctx.config.save()?;
+
+    reconfigure(ctx)
+}
+
+/// Recent interventions for the Status menu
+pub fn report() -> Result<()> {
+    let history = history();
+    if history.is_empty() {
+        log::info("No watchdog interventions recorded")?;
+    } else {
+        let skip = history.len().saturating_sub(20);
+        let text = history
+            .iter()
+            .skip(skip)
+            .map(|record| record.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        log::info(text)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,361 @@
+use crate::imports::*;
+use base64::prelude::*;
+use serde_json::{json, Value};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xa;
+
+/// Upper bound for a single response message
+const MAX_MESSAGE_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Minimal blocking Kaspa wRPC (JSON protocol) client for local
+/// node instances over a plain (non-TLS) WebSocket connection.
+pub struct Client {
+    reader: BufReader<TcpStream>,
+    writer: TcpStream,
+    id: u64,
+}
+
+/// Non-cryptographic random bytes for WebSocket key and frame masks
+fn entropy(seed: u64) -> [u8; 8] {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    xxh3_64(&[nanos.to_le_bytes(), (seed as u128).to_le_bytes()].concat()).to_le_bytes()
+}
+
+impl Client {
+    /// Connect and perform the WebSocket handshake; `timeout` applies
+    /// to the connection and to each subsequent read and write.
+    pub fn connect(addr: SocketAddr, timeout: Duration) -> Result<Self> {
+        let stream = TcpStream::connect_timeout(&addr, timeout)?;
+        stream.set_read_timeout(Some(timeout))?;
+        stream.set_write_timeout(Some(timeout))?;
+        stream.set_nodelay(true)?;
+
+        let key = BASE64_STANDARD.encode([entropy(0), entropy(1)].concat());
+        let mut writer = stream.try_clone()?;
+        write!(
+            writer,
+            "GET / HTTP/1.1\r\nHost: {addr}\r\nUpgrade: websocket\r\nConnection: Upgrade\r\nSec-WebSocket-Key: {key}\r\nSec-WebSocket-Version: 13\r\n\r\n"
+        )?;
+
+        let mut reader = BufReader::new(stream);
+        let mut status = String::new();
+        reader.read_line(&mut status)?;
+        if !status.starts_with("HTTP/1.1 101") {
+            return Err(Error::custom(format!(
+                "wRPC handshake failed: {}",
+                status.trim()
+            )));
+        }
+        // skip response headers
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                break;
+            }
+        }
+
+        Ok(Self {
+            reader,
+            writer,
+            id: 0,
+        })
+    }
+
+    fn write_frame(&mut self, opcode: u8, payload: &[u8]) -> Result<()> {
+        let mut frame = vec![0x80 | opcode];
+        let len = payload.len();
+        if len < 126 {
+            frame.push(0x80 | len as u8);
+        } else if len <= u16::MAX as usize {
+            frame.push(0x80 | 126);
+            frame.extend_from_slice(&(len as u16).to_be_bytes());
+        } else {
+            frame.push(0x80 | 127);
+            frame.extend_from_slice(&(len as u64).to_be_bytes());
+        }
+        let mask = entropy(self.id);
+        frame.extend_from_slice(&mask[..4]);
+        frame.extend(payload.iter().enumerate().map(|(i, b)| b ^ mask[i % 4]));
+        self.writer.write_all(&frame)?;
+        Ok(())
+    }
+
+    fn read_frame(&mut self) -> Result<(bool, u8, Vec<u8>)> {
+        let mut header = [0u8; 2];
+        self.reader.read_exact(&mut header)?;
+        let fin = header[0] & 0x80 != 0;
+        let opcode = header[0] & 0x0f;
+        let masked = header[1] & 0x80 != 0;
+        let len = match header[1] & 0x7f {
+            126 => {
+                let mut len = [0u8; 2];
+                self.reader.read_exact(&mut len)?;
+                u16::from_be_bytes(len) as u64
+            }
+            127 => {
+                let mut len = [0u8; 8];
+                self.reader.read_exact(&mut len)?;
+                u64::from_be_bytes(len)
+            }
+            len => len as u64,
+        };
+        if len > MAX_MESSAGE_SIZE {
+            return Err(Error::custom("wRPC message exceeds size limit"));
+        }
+        let mut mask = [0u8; 4];
+        if masked {
+            self.reader.read_exact(&mut mask)?;
+        }
+        let mut payload = vec![0u8; len as usize];
+        self.reader.read_exact(&mut payload)?;
+        if masked {
+            payload
+                .iter_mut()
+                .enumerate()
+                .for_each(|(i, b)| *b ^= mask[i % 4]);
+        }
+        Ok((fin, opcode, payload))
+    }
+
+    /// Read the next complete data message, answering pings
+    fn read_message(&mut self) -> Result<Vec<u8>> {
+        let mut message = Vec::new();
+        loop {
+            let (fin, opcode, payload) = self.read_frame()?;
+            match opcode {
+                OPCODE_TEXT | OPCODE_BINARY | OPCODE_CONTINUATION => {
+                    message.extend(payload);
+                    if message.len() as u64 > MAX_MESSAGE_SIZE {
+                        return Err(Error::custom("wRPC message exceeds size limit"));
+                    }
+                    if fin {
+                        return Ok(message);
+                    }
+                }
+                OPCODE_PING => self.write_frame(OPCODE_PONG, &payload)?,
+                OPCODE_PONG => {}
+                OPCODE_CLOSE => return Err(Error::custom("wRPC connection closed")),
+                _ => return Err(Error::custom(format!("Unsupported opcode {opcode}"))),
+            }
+        }
+    }
+
+    /// Invoke an RPC method and return the response `params`
+    pub fn call(&mut self, method: &str, params: Value) -> Result<Value> {
+        self.id += 1;
+        let id = self.id;
+        let request = json!({ "id": id, "method": method, "params": params });
+        self.write_frame(OPCODE_TEXT, request.to_string().as_bytes())?;
+
+        loop {
+            let message: Value = serde_json::from_slice(&self.read_message()?)?;
+            // skip notifications and stale responses
+            if message.get("id").and_then(Value::as_u64) != Some(id) {
+                continue;
+            }
+            if let Some(error) = message.get("error").filter(|error| !error.is_null()) {
+                return Err(Error::custom(format!("wRPC `{method}` failed: {error}")));
+            }
+            return Ok(message.get("params").cloned().unwrap_or(Value::Null));
+        }
+    }
+
+    pub fn close(mut self) {
+        self.write_frame(OPCODE_CLOSE, &[]).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::thread;
+
+    const TIMEOUT: Duration = Duration::from_millis(500);
+
+    /// Server side of a WebSocket session (unmasked frames)
+    struct Stub {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    impl Stub {
+        fn accept(listener: TcpListener) -> Self {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request = Vec::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                request.push(line);
+            }
+            assert!(request[0].starts_with("GET / HTTP/1.1"));
+            assert!(request
+                .iter()
+                .any(|line| line.starts_with("Sec-WebSocket-Key:")));
+            write!(
+                writer,
+                "HTTP/1.1 101 Switching Protocols\r\nUpgrade: websocket\r\nConnection: Upgrade\r\n\r\n"
+            )
+            .unwrap();
+            Self { reader, writer }
+        }
+
+        /// Read a masked client frame
+        fn read(&mut self) -> (u8, Vec<u8>) {
+            let mut header = [0u8; 2];
+            self.reader.read_exact(&mut header).unwrap();
+            assert_ne!(header[1] & 0x80, 0, "client frames must be masked");
+            let len = match header[1] & 0x7f {
+                126 => {
+                    let mut len = [0u8; 2];
+                    self.reader.read_exact(&mut len).unwrap();
+                    u16::from_be_bytes(len) as usize
+                }
+                len => len as usize,
+            };
+            let mut mask = [0u8; 4];
+            self.reader.read_exact(&mut mask).unwrap();
+            let mut payload = vec![0u8; len];
+            self.reader.read_exact(&mut payload).unwrap();
+            payload
+                .iter_mut()
+                .enumerate()
+                .for_each(|(i, b)| *b ^= mask[i % 4]);
+            (header[0] & 0x0f, payload)
+        }
+
+        fn request(&mut self) -> Value {
+            let (opcode, payload) = self.read();
+            assert_eq!(opcode, OPCODE_TEXT);
+            serde_json::from_slice(&payload).unwrap()
+        }
+
+        fn write(&mut self, fin: bool, opcode: u8, payload: &[u8]) {
+            let mut frame = vec![if fin { 0x80 } else { 0 } | opcode];
+            if payload.len() < 126 {
+                frame.push(payload.len() as u8);
+            } else {
+                frame.push(126);
+                frame.extend_from_slice(&(payload.len() as u16).to_be_bytes());
+            }
+            frame.extend_from_slice(payload);
+            self.writer.write_all(&frame).unwrap();
+        }
+    }
+
+    fn listen() -> (TcpListener, SocketAddr) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        (listener, addr)
+    }
+
+    #[test]
+    fn calls_methods() {
+        let (listener, addr) = listen();
+        let server = thread::spawn(move || {
+            let mut stub = Stub::accept(listener);
+
+            let request = stub.request();
+            assert_eq!(request["method"], "getServerInfo");
+            // a ping and a notification precede the response
+            stub.write(true, OPCODE_PING, b"ping");
+            assert_eq!(stub.read(), (OPCODE_PONG, b"ping".to_vec()));
+            stub.write(true, OPCODE_TEXT, br#"{"method":"notify","params":{}}"#);
+            let response = json!({
+                "id": request["id"],
+                "params": { "serverVersion": "0.16.1", "networkId": "mainnet", "padding": "x".repeat(200) },
+            })
+            .to_string();
+            let (first, second) = response.as_bytes().split_at(64);
+            stub.write(false, OPCODE_TEXT, first);
+            stub.write(true, OPCODE_CONTINUATION, second);
+
+            let request = stub.request();
+            assert_eq!(request["method"], "getSyncStatus");
+            let response = json!({ "id": request["id"], "params": { "isSynced": true } });
+            stub.write(true, OPCODE_TEXT, response.to_string().as_bytes());
+
+            assert_eq!(stub.read().0, OPCODE_CLOSE);
+        });
+
+        let mut client = Client::connect(addr, TIMEOUT).unwrap();
+        let info = client.call("getServerInfo", json!({})).unwrap();
+        assert_eq!(info["serverVersion"], "0.16.1");
+        assert_eq!(info["networkId"], "mainnet");
+        let status = client.call("getSyncStatus", json!({})).unwrap();
+        assert_eq!(status["isSynced"], true);
+        client.close();
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn reports_rpc_error() {
+        let (listener, addr) = listen();
+        let server = thread::spawn(move || {
+            let mut stub = Stub::accept(listener);
+            let request = stub.request();
+            let response = json!({ "id": request["id"], "error": { "message": "unknown method" } });
+            stub.write(true, OPCODE_TEXT, response.to_string().as_bytes());
+        });
+
+        let mut client = Client::connect(addr, TIMEOUT).unwrap();
+        let err = client.call("getFoo", json!({})).unwrap_err().to_string();
+        assert!(
+            err.contains("getFoo") && err.contains("unknown method"),
+            "{err}"
+        );
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn times_out_without_reply() {
+        let (listener, addr) = listen();
+        let server = thread::spawn(move || {
+            let mut stub = Stub::accept(listener);
+            stub.request();
+            // keep the connection open past the client timeout
+            thread::sleep(TIMEOUT * 3);
+        });
+
+        let mut client = Client::connect(addr, TIMEOUT).unwrap();
+        let start = std::time::Instant::now();
+        assert!(client.call("getServerInfo", json!({})).is_err());
+        assert!(start.elapsed() < TIMEOUT * 3);
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn rejects_failed_handshake() {
+        let (listener, addr) = listen();
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut line = String::new();
+            while reader.read_line(&mut line).unwrap() > 2 {
+                line.clear();
+            }
+            write!(stream, "HTTP/1.1 404 Not Found\r\n\r\n").unwrap();
+        });
+
+        let err = Client::connect(addr, TIMEOUT).err().unwrap().to_string();
+        assert!(err.contains("404"), "{err}");
+        server.join().unwrap();
+    }
+}